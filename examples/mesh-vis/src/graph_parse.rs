@@ -44,8 +44,10 @@ pub fn parse_update(yaml: &Yaml) -> anyhow::Result<RouteUpdate<GraphSystem>> {
             data: Source {
                 addr: u8::from_str(parts[0])?,
                 seqno: u16::from_str(parts[1])?,
+                injected: false,
             },
         },
+        hop_count: 0,
     })
 }
 
@@ -64,7 +66,7 @@ pub fn parse_seqno_pair(yaml: &Yaml) -> anyhow::Result<(u8, u16)> {
 }
 
 pub fn serialize_route(rt: &Route<GraphSystem>, cur_routes: &mut Vec<Yaml>) {
-    let Source { addr, seqno } = rt.source.data;
+    let Source { addr, seqno, .. } = rt.source.data;
     cur_routes.push(Yaml::from_str(
         format!(
             "{addr} {} {seqno} {} {}",
@@ -82,6 +84,7 @@ pub fn parse_route(route: &str) -> anyhow::Result<Route<GraphSystem>> {
         data: Source {
             addr: u8::from_str(values[0])?,
             seqno: u16::from_str(values[2])?,
+            injected: false,
         },
     };
     ensure!(values.len() == 5, "Expected five elements in route");
@@ -94,6 +97,7 @@ pub fn parse_route(route: &str) -> anyhow::Result<Route<GraphSystem>> {
         next_hop,
         fd,
         link: next_hop,
+        hop_count: 0,
         retracted: false
     })
 }
@@ -148,6 +152,19 @@ pub fn load(state: &Yaml) -> anyhow::Result<State> {
                                     from,
                                 ));
                             }
+                            "mru" => {
+                                let mut val: Vec<RouteUpdate<GraphSystem>> = Vec::new();
+                                for udt in v.as_vec().context("Expected list of updates")? {
+                                    val.push(parse_update(udt)?)
+                                }
+                                let values = packets.entry(addr).or_default();
+                                values.push((
+                                    DummyMAC {
+                                        data: Packet::MergedRouteUpdate { routes: val },
+                                    },
+                                    from,
+                                ));
+                            }
                             "seqr" => {
                                 let values = packets.entry(addr).or_default();
                                 let pair = parse_seqno_pair(v)?;
@@ -208,7 +225,8 @@ pub fn load(state: &Yaml) -> anyhow::Result<State> {
                 sys.router.links.insert(*neigh, Neighbour{
                     metric: *metric,
                     addr: *neigh,
-                    routes: HashMap::new()
+                    routes: Default::default(),
+                    protocol: Default::default()
                 });
             }
             nodes.push(sys);
@@ -314,12 +332,22 @@ pub fn save(state: &State) -> Yaml {
                     batch.sort_by(|a, b| a.partial_cmp(b).unwrap());
                     pkt_map.insert(Yaml::from_str("bru"), Yaml::Array(batch));
                 }
+                Packet::MergedRouteUpdate { routes } => {
+                    let merged = routes.iter().map(serialize_update).collect::<Vec<Yaml>>();
+                    pkt_map.insert(Yaml::from_str("mru"), Yaml::Array(merged));
+                }
                 Packet::SeqnoRequest { source, seqno } => {
                     pkt_map.insert(
                         Yaml::from_str("seqr"),
                         serialize_seqno_pair(*source, *seqno),
                     );
                 }
+                Packet::ClassPacket { .. } => {} // only the default traffic class is visualized
+                Packet::AckRequest { .. } | Packet::Ack { .. } => {} // the visualized network does not request acks
+                Packet::Hello { .. }
+                | Packet::Extension { .. }
+                | Packet::AttributeUpdate { .. }
+                | Packet::LinkStateUpdate { .. } => {}
             }
             packets.push(Yaml::Hash(pkt_map));
         }
@@ -11,7 +11,7 @@ use hyper_staticfile::Static;
 use hyper_util::rt::TokioIo;
 use mime_guess::Mime;
 use root::framework::RoutingSystem;
-use root::router::{Router, DummyMAC, NoMACSystem};
+use root::router::{Router, DummyMAC, LowestMetric, NoMACSystem};
 use std::fs::File;
 use std::io;
 use std::io::{BufRead, Error};
@@ -37,6 +37,8 @@ impl RoutingSystem for GraphSystem {
     type NodeAddress = u8;
     type Link = u8;
     type MACSystem = NoMACSystem;
+    type RouteSelector = LowestMetric;
+    type Extension = ();
 }
 
 #[tokio::main]
@@ -45,7 +45,7 @@ pub fn tick_state(state: &mut State) {
 
         // push all outgoing packets from handling packets
 
-        for packet in node.router.outbound_packets.drain(..) {
+        for packet in node.router.take_outbound_packets() {
             // println!("[dbg] OP {} -> {}: {}", node.router.address, packet.addr_phy, json!(packet.packet));
             let values = state.packets.entry(packet.link).or_default();
             values.push((packet.packet, node.router.address))
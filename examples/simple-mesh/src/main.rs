@@ -65,7 +65,8 @@ async fn main() -> anyhow::Result<()> {
             Neighbour {
                 addr: netlink.neigh_node.clone(),
                 metric: INF,
-                routes: HashMap::new(),
+                routes: Default::default(),
+                protocol: Default::default(),
             },
         );
     }
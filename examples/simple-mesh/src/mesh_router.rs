@@ -231,6 +231,8 @@ fn main_loop(
                 for lid in ps.links.keys(){
                     mqs.main.send(DispatchPingLink {link_id: *lid})?;
                 }
+                ps.router.retransmit();
+                write_routing_packets(&mut ps, &mut os, mqs.clone())?;
             }
             Shutdown => {
                 mqs.cancellation_token.cancel()
@@ -274,6 +276,12 @@ fn main_loop(
         for warn in ps.router.warnings.drain(..){
             warn!("{warn:?}");
         }
+
+        if cfg!(debug_assertions) {
+            for violation in ps.router.check_invariants(){
+                error!("Inconsistent router state: {violation:?}");
+            }
+        }
     }
 
     info!("The router has shutdown, saving state...");
@@ -348,7 +356,8 @@ fn handle_packet(
                     Neighbour {
                         addr: node_id.clone(),
                         metric: INF,
-                        routes: HashMap::new(),
+                        routes: Default::default(),
+                        protocol: Default::default(),
                     },
                 );
                 ps.links.insert(link_id, net_link);
@@ -403,7 +412,7 @@ fn handle_packet(
 fn write_routing_packets(ps: &mut PersistentState,
                                os: &mut OperatingState,
                                mq: MessageQueue) -> anyhow::Result<()> {
-    for pkt in ps.router.outbound_packets.drain(..){
+    for pkt in ps.router.take_outbound_packets(){
         if let Some(netlink) = ps.links.get(&pkt.link){
             mq.outbound.send(
                 QueuedPacket{
@@ -546,10 +555,11 @@ fn handle_command(
             rtable.push(format!("Self: {}, seq: {}", ps.router.address, ps.router.seqno));
             for (addr, route) in &ps.router.routes {
                 rtable.push(
-                    format!("{addr} - via: {}, nh: {}, c: {}, seq: {}, fd: {}, ret: {}",
+                    format!("{addr} - via: {}, nh: {}, c: {}, hops: {}, seq: {}, fd: {}, ret: {}",
                             route.link,
                             route.next_hop.clone(),
                             route.metric,
+                            route.hop_count,
                             route.source.data.seqno,
                             route.fd,
                             route.retracted
@@ -655,7 +665,8 @@ fn handle_command(
                     Neighbour {
                         addr: netlink.neigh_node.clone(),
                         metric: INF,
-                        routes: HashMap::new(),
+                        routes: Default::default(),
+                        protocol: Default::default(),
                     },
                 );
                 let node_addr = ps.router.address.clone();
@@ -1,10 +1,21 @@
 use uuid::Uuid;
 use root::framework::RoutingSystem;
-use root::router::NoMACSystem;
+use root::router::{LowestMetric, NoMACSystem};
 
 pub struct IPV4System {}
 impl RoutingSystem for IPV4System {
     type NodeAddress = String;
     type Link = Uuid;
     type MACSystem = NoMACSystem;
+    type RouteSelector = LowestMetric;
+    type Extension = ();
+    // metrics are round trip times in milliseconds, which jitter
+    const SWITCH_THRESHOLD: u16 = 5;
+    // full updates happen every 10 seconds
+    const SWITCH_HOLD_ROUNDS: u64 = 2;
+    // peers behind an unreliable link stop being advertised after 3 quick drops, for about a minute
+    const FLAP_PENALTY: u32 = 1000;
+    const FLAP_HALF_LIFE_ROUNDS: u32 = 3;
+    // urgent updates are resent on the ping timer, until the neighbour acknowledges them
+    const REQUEST_ACKS: bool = true;
 }
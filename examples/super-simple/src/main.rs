@@ -3,13 +3,15 @@ use root::concepts::neighbour::Neighbour;
 use root::concepts::packet::OutboundPacket;
 use root::concepts::route::Route;
 use root::framework::RoutingSystem;
-use root::router::{NoMACSystem, Router};
+use root::router::{LowestMetric, NoMACSystem, Router};
 
 struct SimpleExample {} // just a type to inform root of your network parameters
 impl RoutingSystem for SimpleExample{
     type NodeAddress = String; // our nodes have string names
     type Link = i32;
     type MACSystem = NoMACSystem; // we won't use MAC for this example
+    type RouteSelector = LowestMetric;
+    type Extension = ();
 }
 
 fn main() {
@@ -34,7 +36,7 @@ fn main() {
 
     for step in 0..3 {
         // collect all of our packets, if any
-        let packets: Vec<OutboundPacket<SimpleExample>> = nodes.iter_mut().flat_map(|(_id, node)| node.outbound_packets.drain(..)).collect();
+        let packets: Vec<OutboundPacket<SimpleExample>> = nodes.iter_mut().flat_map(|(_id, node)| node.take_outbound_packets()).collect();
 
         for OutboundPacket{link, dest, packet} in packets{
             // deliver the routing packet. in this simple example, the link isn't really used
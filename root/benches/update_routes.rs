@@ -0,0 +1,68 @@
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use root::concepts::neighbour::Neighbour;
+use root::concepts::packet::{Packet, RouteUpdate};
+use root::concepts::route::{ExternalRoute, Source};
+use root::framework::RoutingSystem;
+use root::router::{DummyMAC, LowestMetric, NoMACSystem, Router};
+
+const DESTINATIONS: u32 = 10_000;
+const NEIGHBOURS: u32 = 4;
+
+struct BenchSystem {}
+impl RoutingSystem for BenchSystem {
+    type NodeAddress = u32;
+    type Link = u32;
+    type MACSystem = NoMACSystem;
+    type RouteSelector = LowestMetric;
+    type Extension = ();
+}
+
+/// a router with a few neighbours, each advertising every destination
+fn converged_router() -> Router<BenchSystem> {
+    let mut router = Router::new(0);
+    for link in 0..NEIGHBOURS {
+        let mut neigh = Neighbour::new(DESTINATIONS + link);
+        neigh.metric = link as u16 + 1;
+        for dest in 1..=DESTINATIONS {
+            neigh.routes.insert(dest, ExternalRoute {
+                source: DummyMAC::from(Source { addr: dest, seqno: 0, injected: false }),
+                metric: (dest % 100) as u16 + link as u16,
+                hop_count: 0,
+                retracted: false,
+            });
+        }
+        router.links.insert(link, neigh);
+    }
+    router.update_routes();
+    router.update_routes();
+    router.take_outbound_packets();
+    router
+}
+
+fn single_update(dest: u32, metric: u16) -> DummyMAC<Packet<BenchSystem>> {
+    DummyMAC::from(Packet::UrgentRouteUpdate(RouteUpdate {
+        source: DummyMAC::from(Source { addr: dest, seqno: 0, injected: false }),
+        metric,
+        hop_count: 0,
+    }))
+}
+
+fn bench_update_routes(c: &mut Criterion) {
+    let mut group = c.benchmark_group("update_routes_10k");
+    group.bench_function("full_recompute", |b| {
+        b.iter_batched_ref(converged_router, |router| {
+            router.invalidate_routes();
+            router.update_routes();
+        }, BatchSize::LargeInput)
+    });
+    group.bench_function("single_packet", |b| {
+        b.iter_batched_ref(converged_router, |router| {
+            router.handle_packet(&single_update(42, 1), &0, &DESTINATIONS).unwrap();
+            router.update_routes();
+        }, BatchSize::LargeInput)
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_update_routes);
+criterion_main!(benches);
@@ -0,0 +1,17 @@
+use std::collections::BTreeMap;
+use crate::concepts::route::Source;
+use crate::framework::RoutingSystem;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use educe::Educe;
+
+/// The attributes of a node (hostname, role, software version...), signed by the node and versioned by its seqno.
+/// See [Router::set_attributes](crate::router::Router::set_attributes)
+#[derive(Educe)]
+#[educe(Clone(bound()))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(bound = ""))]
+pub struct NodeAttributes<T: RoutingSystem + ?Sized> {
+    /// the node, and its seqno when the attributes were sent
+    pub source: Source<T>,
+    pub attributes: BTreeMap<String, String>,
+}
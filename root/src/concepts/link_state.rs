@@ -0,0 +1,16 @@
+use crate::concepts::route::Source;
+use crate::framework::RoutingSystem;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use educe::Educe;
+
+/// The neighbours of a node, signed by the node and versioned by its seqno. See [RoutingSystem::LINK_STATE]
+#[derive(Educe)]
+#[educe(Clone(bound()))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(bound = ""))]
+pub struct LinkState<T: RoutingSystem + ?Sized> {
+    /// the node, and its seqno when its neighbours were sent
+    pub source: Source<T>,
+    /// (neighbour, metric) of each link that is up, a neighbour over several links appears once for each link
+    pub neighbours: Vec<(T::NodeAddress, u16)>,
+}
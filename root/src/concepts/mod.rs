@@ -1,3 +1,5 @@
+pub mod attributes;
+pub mod link_state;
 pub mod neighbour;
 pub mod packet;
 pub mod route;
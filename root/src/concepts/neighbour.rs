@@ -1,11 +1,11 @@
-use std::collections::HashMap;
-use cfg_if::cfg_if;
 use educe::Educe;
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
+use crate::concepts::packet::Capabilities;
 use crate::concepts::route::{ExternalRoute};
 use crate::framework::{RoutingSystem};
+use crate::util::RootMap;
 
 #[derive(Educe)]
 #[educe(Clone(bound()))]
@@ -13,10 +13,33 @@ use crate::framework::{RoutingSystem};
 pub struct Neighbour<T: RoutingSystem + ?Sized> {
     /// the routing network address
     pub addr: T::NodeAddress,
-    pub routes: HashMap<T::NodeAddress, ExternalRoute<T>>,
+    pub routes: RootMap<T::NodeAddress, ExternalRoute<T>>,
     /// Direct Link-metric to this neighbour, 0xFFFF for Infinity. Lower is better.
     /// INF if the link is down
-    pub metric: u16
+    pub metric: u16,
+    /// the protocol spoken by the neighbour, as learnt from its hello
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub protocol: NeighbourProtocol,
+}
+
+/// What we know about the protocol spoken by a neighbour, see [Packet::Hello](crate::concepts::packet::Packet::Hello)
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum NeighbourProtocol {
+    /// the neighbour has not answered our hellos yet, and is spoken to as if it runs protocol version 0
+    Unknown {
+        hellos_sent: u32,
+    },
+    Known {
+        version: u16,
+        capabilities: Capabilities,
+    },
+}
+
+impl Default for NeighbourProtocol {
+    fn default() -> Self {
+        NeighbourProtocol::Unknown { hellos_sent: 0 }
+    }
 }
 
 impl<T: RoutingSystem + ?Sized> Neighbour<T>{
@@ -25,6 +48,23 @@ impl<T: RoutingSystem + ?Sized> Neighbour<T>{
             addr,
             routes: Default::default(),
             metric: 1,
+            protocol: Default::default(),
+        }
+    }
+
+    /// the protocol version spoken by the neighbour, 0 if it is unknown
+    pub fn version(&self) -> u16 {
+        match self.protocol {
+            NeighbourProtocol::Unknown { .. } => 0,
+            NeighbourProtocol::Known { version, .. } => version,
+        }
+    }
+
+    /// the parts of the protocol that the neighbour supports, none if they are unknown
+    pub fn capabilities(&self) -> Capabilities {
+        match self.protocol {
+            NeighbourProtocol::Unknown { .. } => Capabilities::NONE,
+            NeighbourProtocol::Known { capabilities, .. } => capabilities,
         }
     }
 }
\ No newline at end of file
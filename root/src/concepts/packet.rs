@@ -2,8 +2,43 @@ use educe::Educe;
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
+use crate::concepts::attributes::NodeAttributes;
+use crate::concepts::link_state::LinkState;
 use crate::concepts::route::Source;
-use crate::framework::{MAC, RoutingSystem};
+use crate::framework::{MAC, MACSignature, RoutingSystem};
+
+/// The version of the routing protocol spoken by this release of root, exchanged in [Packet::Hello].
+/// Neighbours that never sent a hello are assumed to speak version 0, which has no capabilities.
+pub const PROTOCOL_VERSION: u16 = 1;
+
+/// Optional parts of the protocol, as a set of flags. Packets that a neighbour does not support are downgraded or not sent to it
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Capabilities(pub u32);
+
+impl Capabilities {
+    pub const NONE: Capabilities = Capabilities(0);
+    /// [Packet::MergedRouteUpdate]
+    pub const MERGED_UPDATES: Capabilities = Capabilities(1);
+    /// [Packet::AckRequest] and [Packet::Ack]
+    pub const ACKS: Capabilities = Capabilities(1 << 1);
+    /// [Packet::ClassPacket]
+    pub const CLASSES: Capabilities = Capabilities(1 << 2);
+    /// [Packet::Extension]
+    pub const EXTENSIONS: Capabilities = Capabilities(1 << 3);
+    /// [Packet::AttributeUpdate]
+    pub const ATTRIBUTES: Capabilities = Capabilities(1 << 4);
+    /// [Packet::LinkStateUpdate]
+    pub const LINK_STATE: Capabilities = Capabilities(1 << 5);
+    /// every capability of this release
+    pub const ALL: Capabilities = Capabilities(
+        Self::MERGED_UPDATES.0 | Self::ACKS.0 | Self::CLASSES.0 | Self::EXTENSIONS.0 | Self::ATTRIBUTES.0 | Self::LINK_STATE.0
+    );
+
+    pub fn contains(self, other: Capabilities) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
 
 #[derive(Educe)]
 #[educe(Clone(bound()))]
@@ -13,12 +48,69 @@ pub enum Packet<T: RoutingSystem + ?Sized> {
     UrgentRouteUpdate(RouteUpdate<T>),
     /// this is a batch, full-table update that should only be sent periodically to all nodes
     BatchRouteUpdate { routes: Vec<RouteUpdate<T>> },
+    /// several urgent updates for different sources, that are handled as if they were sent separately
+    MergedRouteUpdate { routes: Vec<RouteUpdate<T>> },
     SeqnoRequest {
         /// the source to request information for
         source: T::NodeAddress,
         /// the seqno of the request
         seqno: u16,
     },
+    /// an urgent packet, that the receiver acknowledges with an [Packet::Ack] of the same nonce
+    AckRequest {
+        nonce: u32,
+        packet: Box<MAC<Packet<T>, T>>,
+    },
+    Ack {
+        nonce: u32,
+    },
+    /// sent to a neighbour when its link is established, to agree on the parts of the protocol that both routers support
+    Hello {
+        version: u16,
+        capabilities: Capabilities,
+        /// whether this answers a hello from the receiver, other hellos are always answered
+        answer: bool,
+    },
+    /// a packet of a traffic class other than the default, see [Router::classes](crate::router::Router::classes)
+    ClassPacket {
+        class: u8,
+        packet: Box<MAC<Packet<T>, T>>,
+    },
+    /// application data for the receiver, see [Router::extensions](crate::router::Router::extensions)
+    Extension {
+        data: T::Extension,
+    },
+    /// the attributes of nodes in the network, each signed by its node, see [Router::node_attributes](crate::router::Router::node_attributes)
+    AttributeUpdate {
+        attributes: Vec<MAC<NodeAttributes<T>, T>>,
+    },
+    /// the neighbours of nodes in the network, each signed by its node, see [Router::topology](crate::router::Router::topology)
+    LinkStateUpdate {
+        states: Vec<MAC<LinkState<T>, T>>,
+    },
+}
+
+/// The order in which packets should be sent, lower first
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum PacketPriority {
+    /// retractions, seqno updates and seqno requests
+    Urgent,
+    /// full-table updates, extensions, attributes and link states
+    Periodic,
+}
+
+impl<T: RoutingSystem + ?Sized> Packet<T> {
+    pub fn priority(&self) -> PacketPriority {
+        match self {
+            Packet::BatchRouteUpdate { .. }
+            | Packet::Extension { .. }
+            | Packet::AttributeUpdate { .. }
+            | Packet::LinkStateUpdate { .. } => PacketPriority::Periodic,
+            Packet::ClassPacket { packet, .. } | Packet::AckRequest { packet, .. } => packet.data().priority(),
+            _ => PacketPriority::Urgent,
+        }
+    }
 }
 
 #[derive(Educe)]
@@ -28,6 +120,10 @@ pub struct RouteUpdate<T: RoutingSystem + ?Sized> {
     /// Secured source information signed by the source (address, seqno)
     pub source: MAC<Source<T>, T>,
     pub metric: u16,
+    /// number of hops from the sender to the source, 0 if the sender is the source. Peers that do not send it are assumed to be the source.
+    /// It is left out when it is 0, so that updates sent to version 0 peers serialize as they did in version 0
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "is_zero"))]
+    pub hop_count: u8,
 }
 
 #[derive(Educe)]
@@ -39,3 +135,25 @@ pub struct OutboundPacket<T: RoutingSystem + ?Sized> {
     pub dest: T::NodeAddress,
     pub packet: MAC<Packet<T>, T>,
 }
+
+/// An extension received from a neighbour, see [Packet::Extension]
+#[derive(Educe)]
+#[educe(Clone(bound()))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(bound = ""))]
+pub struct ReceivedExtension<T: RoutingSystem + ?Sized> {
+    /// received via this link
+    pub link: T::Link,
+    pub neighbour: T::NodeAddress,
+    pub data: T::Extension,
+}
+
+impl<T: RoutingSystem + ?Sized> OutboundPacket<T> {
+    pub fn priority(&self) -> PacketPriority {
+        self.packet.data().priority()
+    }
+}
+
+#[cfg(feature = "serde")]
+fn is_zero(value: &u8) -> bool {
+    *value == 0
+}
@@ -1,8 +1,6 @@
 use crate::framework::{MAC, RoutingSystem};
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
-use std::fmt::{Display, Formatter};
-use std::hash::{Hash, Hasher};
 use educe::Educe;
 
 #[derive(Educe)]
@@ -19,6 +17,9 @@ pub struct Route<T: RoutingSystem + ?Sized> {
     pub link: T::Link,
     /// the next-hop address of this route
     pub next_hop: T::NodeAddress,
+    /// the number of hops to the source
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub hop_count: u8,
     /// whether this route has been retracted, if it has, do not retract again
     pub retracted: bool
 }
@@ -31,6 +32,9 @@ pub struct ExternalRoute<T: RoutingSystem + ?Sized> {
     pub source: MAC<Source<T>, T>,
     /// the metric with which this route was advertised by the neighbour, or FFFF hexadecimal (infinity) for a recently retracted route
     pub metric: u16,
+    /// the number of hops from the neighbour to the source, as advertised by the neighbour
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub hop_count: u8,
     /// whether this route has been retracted, if it has, do not retract again
     pub retracted: bool
 }
@@ -41,4 +45,26 @@ pub struct ExternalRoute<T: RoutingSystem + ?Sized> {
 pub struct Source<T: RoutingSystem + ?Sized> {
     pub addr: T::NodeAddress,
     pub seqno: u16,
+    /// whether the address is injected by a static route, rather than being the address of the node that signed it.
+    /// It is left out when false, so that the signatures of other sources are the same as in protocol version 0
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "std::ops::Not::not"))]
+    pub injected: bool,
+}
+
+/// A route injected into the router rather than learned from neighbours, see [Router::add_static_route](crate::router::Router::add_static_route)
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct StaticRoute {
+    /// the metric with which the route is advertised
+    pub metric: u16,
+    /// the administrative distance, compared with [RoutingSystem::LEARNED_DISTANCE]
+    pub distance: u8,
+    /// the seqno with which the route is advertised, shared with the other routers that inject the same address
+    pub seqno: u16,
+    /// whether the route is used by this router
+    pub active: bool,
+    /// whether the route is advertised. A preferred route to a node that is reachable is only used by this router,
+    /// so that it does not compete with the node for its own address
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub advertised: bool,
 }
@@ -5,7 +5,7 @@ use crate::framework::RoutingSystem;
 /// These are critical errors that may affect the stability or security of the application
 #[derive(Error)]
 #[derive(Educe)]
-#[educe(Debug)]
+#[educe(Debug, Clone(bound()))]
 pub enum RoutingError<T: RoutingSystem + ?Sized> {
     /// This can either be due to packet forgery, MITM, or system misconfiguration
     #[error("Rejected packet over link, MAC Validation Failed.")]
@@ -24,11 +24,52 @@ pub enum RoutingWarning<T: RoutingSystem + ?Sized>{
     MetricIsZero{
         link: T::Link
     },
-    /// This warning is triggered when a neighbour requests a seqno update (that has passed MAC validation), where the requested seqno > cur_seqno + 1.
+    /// This warning is triggered when a neighbour requests a seqno update (that has passed MAC validation), where the requested seqno > cur_seqno + 1,
+    /// or advertises a route to this node with a seqno > cur_seqno.
     /// Depending on TRUST_RESYNC_SEQNO, root will automatically trust this seqno request and synchronize the seqno with the requested seqno.
     /// NOTE: This might be an indication of node data loss!
     DesynchronizedSeqno{
         old_seqno: u16,
         new_seqno: u16
+    },
+    /// The route to this destination has been retracted too often, see [RoutingSystem::FLAP_PENALTY].
+    /// It is advertised as unreachable until its penalty decays, but is still used by this router.
+    RouteSuppressed{
+        addr: T::NodeAddress
+    },
+    /// The neighbour did not acknowledge any urgent update for [RoutingSystem::MAX_RETRANSMISSIONS] retransmissions,
+    /// it may be unreachable or may not support acknowledgements
+    NotAcknowledging{
+        link: T::Link,
+        neighbour: T::NodeAddress
     }
-}
\ No newline at end of file
+}
+/// An inconsistency in the state of a single router, found by [Router::check_invariants](crate::router::Router::check_invariants).
+/// These indicate a bug in root, or state that was modified directly.
+#[derive(Educe)]
+#[educe(Debug, Clone(bound()))]
+pub enum ConsistencyViolation<T: RoutingSystem + ?Sized>{
+    /// A route that is not retracted goes over a link that is not in `links`
+    MissingLink{
+        source: T::NodeAddress,
+        link: T::Link
+    },
+    /// The next hop of a route is not the neighbour on the other end of its link
+    WrongNextHop{
+        source: T::NodeAddress,
+        next_hop: T::NodeAddress,
+        neighbour: T::NodeAddress
+    },
+    /// The feasibility distance of a route that is not retracted is larger than its metric
+    FeasibilityDistanceAboveMetric{
+        source: T::NodeAddress,
+        fd: u16,
+        metric: u16
+    },
+    /// The routing table has a route to the router itself
+    RouteToSelf,
+    /// A forwarded seqno request is for a source that the router has no route to
+    UnknownSeqnoRequest{
+        source: T::NodeAddress
+    }
+}
@@ -8,13 +8,55 @@ cfg_if!{
     }
 }
 
+use crate::concepts::neighbour::Neighbour;
+use crate::concepts::route::Source;
 use crate::router::Router;
 
 pub trait RoutingSystem {
     /// Maximal length that the warning log should be kept for, if the buffer is full, the oldest warning is dropped.
     const MAX_WARN_LENGTH: usize = 1000;
+    /// Maximal number of received extensions that are kept until the application drains them, if the buffer is full, the oldest extension is dropped.
+    const MAX_EXTENSION_LENGTH: usize = 1000;
     /// Should the routing client trust seqno requests where the seqno > cur_seqno + 1. ENSURE MAC IS ENABLED
     const TRUST_RESYNC_SEQNO: bool = true;
+    /// A route over another link replaces the selected route only if its metric is lower by more than this.
+    /// Does not apply when the selected route is unreachable. Leave this at 0 if the [RouteSelector] does not prefer lower metrics.
+    const SWITCH_THRESHOLD: u16 = 0;
+    /// Number of full updates for which a route over another link must stay better than the selected route, before it is selected.
+    /// Does not apply when the selected route is unreachable.
+    const SWITCH_HOLD_ROUNDS: u64 = 0;
+    /// Penalty added to a destination every time its selected route is retracted. 0 disables route flap damping.
+    const FLAP_PENALTY: u32 = 0;
+    /// A destination whose penalty is above this is suppressed, and advertised to neighbours as unreachable
+    const FLAP_SUPPRESS_THRESHOLD: u32 = 2000;
+    /// A suppressed destination is advertised again once its penalty decays below this
+    const FLAP_REUSE_THRESHOLD: u32 = 750;
+    /// Number of full updates after which the penalty of a destination has decayed by half
+    const FLAP_HALF_LIFE_ROUNDS: u32 = 4;
+    /// Routes with more hops than this are treated as unreachable
+    const MAX_DIAMETER: u8 = u8::MAX;
+    /// Ask neighbours to acknowledge urgent updates, which are resent by [Router::retransmit] until they are
+    const REQUEST_ACKS: bool = false;
+    /// Number of retransmissions after which a neighbour that acknowledges nothing is reported, and its updates are dropped
+    const MAX_RETRANSMISSIONS: u32 = 5;
+    /// Number of hellos sent to a neighbour that does not answer, before it is assumed to run a release of root without hellos.
+    /// One hello is sent when the link is established, and one in each full update after that.
+    const HELLO_ATTEMPTS: u32 = 3;
+    /// Whether to flood the neighbours and link metrics of this node to the whole network, so that every node knows the full topology,
+    /// see [Router::topology](crate::router::Router::topology). Forwarding still uses the distance-vector routes.
+    /// The seqno is incremented whenever a link comes up or goes down, and at most once per full update when only link metrics change.
+    /// The topology only passes through routers that enable this.
+    const LINK_STATE: bool = false;
+    /// Whether only the [Router::proactive](crate::router::Router::proactive) destinations are advertised in full updates.
+    /// Routes to other destinations are discovered with [Router::request_route](crate::router::Router::request_route), and expire when unused.
+    const ON_DEMAND: bool = false;
+    /// Number of full updates after which an on-demand route that was not requested is retracted. Its seqno and fd are kept until it is discovered again
+    const ROUTE_EXPIRY_ROUNDS: u64 = 16;
+    /// Number of full updates for which a router waits for the answer to a route discovery, before it can be sent again
+    const DISCOVERY_TIMEOUT_ROUNDS: u64 = 8;
+    /// Administrative distance of the routes learned from neighbours. A static route with a lower distance is preferred over them,
+    /// a static route with a higher or equal distance is only used while there is no learned route. See [Router::add_static_route](crate::router::Router::add_static_route)
+    const LEARNED_DISTANCE: u8 = 120;
     /// Address of the node on the routing network, MUST be globally unique
     type NodeAddress: RootData + RootKey;
     /// A type that describes a physical interface or higher level concept that allows this node to talk to another node via some method
@@ -22,6 +64,20 @@ pub trait RoutingSystem {
     type Link: RootData + RootKey;
     /// An opaque implementation that allows the node to sign packets
     type MACSystem: MACSystem<Self>;
+    /// Chooses between feasible routes to the same destination, use [LowestMetric](crate::router::LowestMetric) for the shortest path.
+    /// This has no default, so existing implementations must add it. [LowestMetric](crate::router::LowestMetric) keeps the behaviour of root 0.1
+    type RouteSelector: RouteSelector<Self>;
+    /// Application data that can be sent to neighbours alongside routing packets, covered by the MAC. Use `()` if unused.
+    /// This has no default, so implementations written for root 0.1 must add it
+    type Extension: RootData;
+
+    /// The address of the area a node belongs to, for hierarchical routing. None puts the node outside of every area, and area addresses
+    /// themselves must return None. Routers only keep routes to the nodes of their own area, and reach other areas through a summary route
+    /// that border routers advertise with the address of their area. The MAC system must accept summaries signed by border routers.
+    /// By default, there are no areas.
+    fn area_of(_addr: &Self::NodeAddress) -> Option<Self::NodeAddress> {
+        None
+    }
 }
 
 cfg_if!{
@@ -34,8 +90,9 @@ cfg_if!{
         impl<T: Clone + Sized> RootData for T {}
     }
 }
-pub trait RootKey: Eq + PartialEq + Hash {}
-impl<T: Eq + PartialEq + Hash> RootKey for T {}
+/// Keys must be totally ordered, as root iterates over them in ascending order
+pub trait RootKey: Eq + PartialEq + Hash + Ord {}
+impl<T: Eq + PartialEq + Hash + Ord> RootKey for T {}
 
 pub trait MACSignature<V: RootData, T: RoutingSystem + ?Sized>: RootData
 {
@@ -50,3 +107,22 @@ pub trait MACSystem<T: RoutingSystem + ?Sized>: Default {
 }
 pub type MAC<V, T> = <<T as RoutingSystem>::MACSystem as MACSystem<T>>::MACSignatureType<V>;
 
+
+/// A feasible route to a destination, as seen by a [RouteSelector]
+pub struct RouteCandidate<'a, T: RoutingSystem + ?Sized> {
+    pub link: &'a T::Link,
+    pub neighbour: &'a Neighbour<T>,
+    pub source: &'a MAC<Source<T>, T>,
+    pub metric: u16,
+    pub hop_count: u8,
+}
+
+/// Decides which route to a destination is selected.
+///
+/// Only feasible routes are offered, so a selector cannot create routing loops. Since the feasibility distance of a
+/// selected route is its metric, a route with a higher metric only becomes feasible once the destination's seqno increases.
+pub trait RouteSelector<T: RoutingSystem + ?Sized>: Default {
+    /// Whether the candidate should replace the selected route.
+    /// Only asked while the selected route is reachable, and the candidate is over another link. Otherwise, the candidate is always taken.
+    fn prefer(&self, candidate: &RouteCandidate<T>, selected: &RouteCandidate<T>) -> bool;
+}
@@ -3,3 +3,7 @@ pub mod framework;
 pub mod router;
 pub mod util;
 pub mod feedback;
+#[cfg(feature = "sim")]
+pub mod sim;
+#[cfg(feature = "record")]
+pub mod record;
@@ -0,0 +1,326 @@
+use std::collections::BTreeMap;
+use std::io;
+use std::io::{BufRead, Write};
+use std::ops::Deref;
+use educe::Educe;
+use serde::{Deserialize, Serialize};
+use crate::concepts::neighbour::Neighbour;
+use crate::concepts::packet::{OutboundPacket, Packet};
+use crate::concepts::route::Route;
+use crate::feedback::{RoutingError, RoutingWarning};
+use crate::framework::{MAC, RoutingSystem};
+use crate::router::{FrozenRouter, Router, ThawedRouter};
+
+/// Something that changed the state of a recorded router
+#[derive(Educe)]
+#[educe(Clone(bound()))]
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "")]
+pub enum RouterInput<T: RoutingSystem + ?Sized> {
+    HandlePacket {
+        packet: MAC<Packet<T>, T>,
+        link: T::Link,
+        neigh: T::NodeAddress,
+    },
+    AddLink {
+        link: T::Link,
+        neigh: T::NodeAddress,
+        metric: u16,
+    },
+    RemoveLink {
+        link: T::Link,
+    },
+    SetLinkMetric {
+        link: T::Link,
+        metric: u16,
+    },
+    Update,
+    FullUpdate,
+    Retransmit,
+    /// the outbound packets were drained, which coalesces them and changes the ack state, see [Router::take_outbound_packets]
+    TakeOutboundPackets,
+    AddStaticRoute {
+        addr: T::NodeAddress,
+        metric: u16,
+        distance: u8,
+    },
+    RemoveStaticRoute {
+        addr: T::NodeAddress,
+    },
+    ServeAnycast {
+        addr: T::NodeAddress,
+    },
+    StopAnycast {
+        addr: T::NodeAddress,
+    },
+    SetAttributes {
+        attributes: BTreeMap<String, String>,
+    },
+    RequestRoute {
+        dest: T::NodeAddress,
+    },
+    AddClass {
+        class: u8,
+    },
+    SetClassLinkMetric {
+        class: u8,
+        link: T::Link,
+        metric: u16,
+    },
+    WriteExtension {
+        link: T::Link,
+        data: T::Extension,
+    },
+    BroadcastExtension {
+        data: T::Extension,
+    },
+}
+
+/// Wraps a router, and writes every input it receives to a log that [Replay] can read back.
+///
+/// The log is made of JSON lines, the first line is the state of the router when recording started.
+/// The router can be read through this wrapper, but must only be changed through it, otherwise the replay diverges.
+pub struct RecordingRouter<T: RoutingSystem, W: Write> {
+    router: Router<T>,
+    writer: W,
+    /// the first error that happened while writing the log, nothing is recorded after it
+    pub error: Option<io::Error>,
+}
+
+impl<T: RoutingSystem, W: Write> RecordingRouter<T, W> {
+    /// starts recording the router, writing its current state to the log
+    pub fn new(router: Router<T>, mut writer: W) -> io::Result<Self> {
+        serde_json::to_writer(&mut writer, &FrozenRouter::new(&router))?;
+        writer.write_all(b"\n")?;
+        Ok(Self {
+            router,
+            writer,
+            error: None,
+        })
+    }
+
+    fn record(&mut self, input: &RouterInput<T>) {
+        if self.error.is_some() {
+            return;
+        }
+        let result = serde_json::to_writer(&mut self.writer, input)
+            .map_err(io::Error::from)
+            .and_then(|_| self.writer.write_all(b"\n"));
+        if let Err(err) = result {
+            self.error = Some(err);
+        }
+    }
+
+    /// stops recording, and returns the router and the log
+    pub fn into_inner(self) -> (Router<T>, W) {
+        (self.router, self.writer)
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    // region Inputs
+
+    pub fn handle_packet(&mut self, packet: &MAC<Packet<T>, T>, link: &T::Link, neigh: &T::NodeAddress) -> Result<(), RoutingError<T>> {
+        self.record(&RouterInput::HandlePacket {
+            packet: packet.clone(),
+            link: link.clone(),
+            neigh: neigh.clone(),
+        });
+        self.router.handle_packet(packet, link, neigh)
+    }
+
+    /// adds a link to a neighbour, replacing any link with the same id
+    pub fn add_link(&mut self, link: T::Link, neigh: T::NodeAddress, metric: u16) {
+        self.record(&RouterInput::AddLink { link: link.clone(), neigh: neigh.clone(), metric });
+        apply_add_link(&mut self.router, link, neigh, metric);
+    }
+
+    pub fn remove_link(&mut self, link: &T::Link) {
+        self.record(&RouterInput::RemoveLink { link: link.clone() });
+        self.router.links.remove(link);
+    }
+
+    pub fn set_link_metric(&mut self, link: &T::Link, metric: u16) {
+        self.record(&RouterInput::SetLinkMetric { link: link.clone(), metric });
+        self.router.set_link_metric(link, metric);
+    }
+
+    pub fn update(&mut self) {
+        self.record(&RouterInput::Update);
+        self.router.update();
+    }
+
+    pub fn full_update(&mut self) {
+        self.record(&RouterInput::FullUpdate);
+        self.router.full_update();
+    }
+
+    pub fn retransmit(&mut self) {
+        self.record(&RouterInput::Retransmit);
+        self.router.retransmit();
+    }
+
+    pub fn add_static_route(&mut self, addr: T::NodeAddress, metric: u16, distance: u8) {
+        self.record(&RouterInput::AddStaticRoute { addr: addr.clone(), metric, distance });
+        self.router.add_static_route(addr, metric, distance);
+    }
+
+    pub fn remove_static_route(&mut self, addr: &T::NodeAddress) {
+        self.record(&RouterInput::RemoveStaticRoute { addr: addr.clone() });
+        self.router.remove_static_route(addr);
+    }
+
+    pub fn serve_anycast(&mut self, addr: T::NodeAddress) {
+        self.record(&RouterInput::ServeAnycast { addr: addr.clone() });
+        self.router.serve_anycast(addr);
+    }
+
+    pub fn stop_anycast(&mut self, addr: &T::NodeAddress) {
+        self.record(&RouterInput::StopAnycast { addr: addr.clone() });
+        self.router.stop_anycast(addr);
+    }
+
+    pub fn set_attributes(&mut self, attributes: BTreeMap<String, String>) {
+        self.record(&RouterInput::SetAttributes { attributes: attributes.clone() });
+        self.router.set_attributes(attributes);
+    }
+
+    pub fn request_route(&mut self, dest: &T::NodeAddress) -> Option<&Route<T>> {
+        self.record(&RouterInput::RequestRoute { dest: dest.clone() });
+        self.router.request_route(dest)
+    }
+
+    /// adds a traffic class, see [Router::add_class]. The class can be read through [Router::class]
+    pub fn add_class(&mut self, class: u8) {
+        self.record(&RouterInput::AddClass { class });
+        self.router.add_class(class);
+    }
+
+    pub fn set_class_link_metric(&mut self, class: u8, link: &T::Link, metric: u16) {
+        self.record(&RouterInput::SetClassLinkMetric { class, link: link.clone(), metric });
+        self.router.set_class_link_metric(class, link, metric);
+    }
+
+    pub fn write_extension(&mut self, link: &T::Link, data: T::Extension) {
+        self.record(&RouterInput::WriteExtension { link: link.clone(), data: data.clone() });
+        self.router.write_extension(link, data);
+    }
+
+    pub fn broadcast_extension(&mut self, data: T::Extension) {
+        self.record(&RouterInput::BroadcastExtension { data: data.clone() });
+        self.router.broadcast_extension(data);
+    }
+
+    /// drains the packets the router wants to send, see [Router::take_outbound_packets].
+    /// Draining coalesces the queued packets and requests acks, so each drain is recorded as an input
+    pub fn take_outbound_packets(&mut self) -> Vec<OutboundPacket<T>> {
+        self.record(&RouterInput::TakeOutboundPackets);
+        self.router.take_outbound_packets()
+    }
+
+    // endregion
+
+    // region Outputs
+
+    /// drains the warnings of the router, this does not affect the replay
+    pub fn take_warnings(&mut self) -> Vec<RoutingWarning<T>> {
+        self.router.warnings.drain(..).collect()
+    }
+
+    // endregion
+}
+
+impl<T: RoutingSystem, W: Write> Deref for RecordingRouter<T, W> {
+    type Target = Router<T>;
+
+    fn deref(&self) -> &Router<T> {
+        &self.router
+    }
+}
+
+fn apply_add_link<T: RoutingSystem>(router: &mut Router<T>, link: T::Link, neigh: T::NodeAddress, metric: u16) {
+    let mut neighbour = Neighbour::new(neigh);
+    neighbour.metric = metric;
+    router.links.insert(link, neighbour);
+}
+
+/// The result of replaying a single input
+pub struct ReplayStep<T: RoutingSystem> {
+    pub input: RouterInput<T>,
+    /// the packets drained by the input, empty unless it is [RouterInput::TakeOutboundPackets]
+    pub outbound_packets: Vec<OutboundPacket<T>>,
+    /// the error returned by the router, if the input was a packet it rejected
+    pub error: Option<RoutingError<T>>,
+}
+
+/// Rebuilds a recorded router from its log, one input at a time
+pub struct Replay<T: RoutingSystem, R: BufRead> {
+    /// the router, as of the last replayed input
+    pub router: Router<T>,
+    lines: io::Lines<R>,
+}
+
+impl<T: RoutingSystem, R: BufRead> Replay<T, R> {
+    /// reads the initial state of the router from the log
+    pub fn new(reader: R) -> io::Result<Self> {
+        let mut lines = reader.lines();
+        let first = lines.next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "the log is empty"))??;
+        let thawed: ThawedRouter<T> = serde_json::from_str(&first)?;
+        Ok(Self {
+            router: thawed.thaw(),
+            lines,
+        })
+    }
+
+    /// replays the next input, returns None at the end of the log
+    pub fn step(&mut self) -> io::Result<Option<ReplayStep<T>>> {
+        let Some(line) = self.lines.next() else {
+            return Ok(None);
+        };
+        let input: RouterInput<T> = serde_json::from_str(&line?)?;
+        let mut error = None;
+        let mut outbound_packets = Vec::new();
+        match input.clone() {
+            RouterInput::HandlePacket { packet, link, neigh } => {
+                error = self.router.handle_packet(&packet, &link, &neigh).err();
+            }
+            RouterInput::AddLink { link, neigh, metric } => apply_add_link(&mut self.router, link, neigh, metric),
+            RouterInput::RemoveLink { link } => {
+                self.router.links.remove(&link);
+            }
+            RouterInput::SetLinkMetric { link, metric } => self.router.set_link_metric(&link, metric),
+            RouterInput::Update => self.router.update(),
+            RouterInput::FullUpdate => self.router.full_update(),
+            RouterInput::Retransmit => self.router.retransmit(),
+            RouterInput::TakeOutboundPackets => outbound_packets = self.router.take_outbound_packets(),
+            RouterInput::AddStaticRoute { addr, metric, distance } => self.router.add_static_route(addr, metric, distance),
+            RouterInput::RemoveStaticRoute { addr } => self.router.remove_static_route(&addr),
+            RouterInput::ServeAnycast { addr } => self.router.serve_anycast(addr),
+            RouterInput::StopAnycast { addr } => self.router.stop_anycast(&addr),
+            RouterInput::SetAttributes { attributes } => self.router.set_attributes(attributes),
+            RouterInput::RequestRoute { dest } => {
+                self.router.request_route(&dest);
+            }
+            RouterInput::AddClass { class } => {
+                self.router.add_class(class);
+            }
+            RouterInput::SetClassLinkMetric { class, link, metric } => self.router.set_class_link_metric(class, &link, metric),
+            RouterInput::WriteExtension { link, data } => self.router.write_extension(&link, data),
+            RouterInput::BroadcastExtension { data } => self.router.broadcast_extension(data),
+        }
+        Ok(Some(ReplayStep {
+            input,
+            outbound_packets,
+            error,
+        }))
+    }
+
+    /// replays the rest of the log, and returns the router
+    pub fn finish(mut self) -> io::Result<Router<T>> {
+        while self.step()?.is_some() {}
+        Ok(self.router)
+    }
+}
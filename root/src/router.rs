@@ -1,15 +1,17 @@
-use crate::concepts::neighbour::Neighbour;
-use crate::concepts::packet::{OutboundPacket, Packet, RouteUpdate};
-use crate::concepts::route::{ExternalRoute, Route, Source};
-use crate::framework::{MAC, MACSignature, MACSystem, RootData, RoutingSystem};
+use crate::concepts::attributes::NodeAttributes;
+use crate::concepts::link_state::LinkState;
+use crate::concepts::neighbour::{Neighbour, NeighbourProtocol};
+use crate::concepts::packet::{Capabilities, OutboundPacket, Packet, ReceivedExtension, RouteUpdate, PROTOCOL_VERSION};
+use crate::concepts::route::{ExternalRoute, Route, Source, StaticRoute};
+use crate::framework::{MAC, MACSignature, MACSystem, RootData, RouteCandidate, RouteSelector, RoutingSystem};
 use crate::router::UpdateAction::{NoAction, Retraction, SeqnoUpdate};
-use crate::util::{increment, increment_by, seqno_less_than, sum_inf};
-use std::collections::{HashMap, HashSet, VecDeque};
+use crate::util::{increment, increment_by, seqno_less_than, sum_inf, RootMap, RootSet};
+use std::collections::{BTreeMap, VecDeque};
 use cfg_if::cfg_if;
 use educe::Educe;
-use crate::feedback::{RoutingError, RoutingWarning};
+use crate::feedback::{ConsistencyViolation, RoutingError, RoutingWarning};
 use crate::feedback::RoutingError::MACValidationFail;
-use crate::feedback::RoutingWarning::{DesynchronizedSeqno, MetricIsZero};
+use crate::feedback::RoutingWarning::{DesynchronizedSeqno, MetricIsZero, NotAcknowledging, RouteSuppressed};
 
 cfg_if!{
     if #[cfg(feature = "serde")] {
@@ -23,21 +25,109 @@ pub const INF: u16 = 0xFFFF;
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(bound = ""), serde_as)]
 pub struct Router<T: RoutingSystem + ?Sized> {
     #[cfg_attr(feature = "serde", serde(skip_serializing, skip_deserializing))]
-    pub links: HashMap<T::Link, Neighbour<T>>,
+    pub links: RootMap<T::Link, Neighbour<T>>,
     /// Source, Route
-    #[serde(skip_serializing, skip_deserializing)]
-    pub routes: HashMap<T::NodeAddress, Route<T>>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing, skip_deserializing))]
+    pub routes: RootMap<T::NodeAddress, Route<T>>,
     pub address: T::NodeAddress,
     #[cfg_attr(feature = "serde", serde_as(as = "Vec<(_, _)>"))]
-    pub seqno_requests: HashMap<T::NodeAddress, u16>,
-    pub broadcast_route_for: HashSet<T::NodeAddress>,
-    pub outbound_packets: Vec<OutboundPacket<T>>,
+    pub seqno_requests: RootMap<T::NodeAddress, u16>,
+    pub broadcast_route_for: RootSet<T::NodeAddress>,
+    /// the destinations that are always advertised with [RoutingSystem::ON_DEMAND], this should be the same on every router
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub proactive: RootSet<T::NodeAddress>,
+    /// the packets written since the last call to [Router::take_outbound_packets], which is the only way to drain them
+    outbound_packets: Vec<OutboundPacket<T>>,
     pub seqno: u16,
+    /// the seqno of the summary route of our area, when this is a border router
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub area_seqno: u16,
+    /// the seqno of the link state of this node, see [RoutingSystem::LINK_STATE]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub link_state_seqno: u16,
+    /// Address -> seqno, the anycast addresses that this node serves, see [Router::serve_anycast]
+    #[cfg_attr(feature = "serde", serde(default), serde_as(as = "Vec<(_, _)>"))]
+    pub anycast: RootMap<T::NodeAddress, u16>,
+    /// Address -> the routes injected into this router, see [Router::add_static_route]
+    #[cfg_attr(feature = "serde", serde(default), serde_as(as = "Vec<(_, _)>"))]
+    pub static_routes: RootMap<T::NodeAddress, StaticRoute>,
+    /// the attributes of this node, see [Router::set_attributes]
+    #[cfg_attr(feature = "serde", serde(default))]
+    attributes: BTreeMap<String, String>,
+    /// whether this node has ever set attributes, it then announces them in every full update, even once they are cleared
+    #[cfg_attr(feature = "serde", serde(default))]
+    announces_attributes: bool,
+    /// Address -> the attributes of other nodes, nodes that become unreachable are removed in the next full update
+    #[cfg_attr(feature = "serde", serde(skip_serializing, skip_deserializing))]
+    pub node_attributes: RootMap<T::NodeAddress, MAC<NodeAttributes<T>, T>>,
+    /// Address -> the neighbours of other nodes, with [RoutingSystem::LINK_STATE]. Nodes that become unreachable are removed in the next full update
+    #[cfg_attr(feature = "serde", serde(skip_serializing, skip_deserializing))]
+    pub topology: RootMap<T::NodeAddress, MAC<LinkState<T>, T>>,
     #[cfg_attr(feature = "serde", serde(skip_serializing, skip_deserializing))]
     pub mac_sys: T::MACSystem,
+    #[cfg_attr(feature = "serde", serde(skip_serializing, skip_deserializing))]
+    pub route_selector: T::RouteSelector,
     /// drain this regularly for warnings
     #[cfg_attr(feature = "serde", serde(skip_serializing, skip_deserializing))]
-    pub warnings: VecDeque<RoutingWarning<T>>
+    pub warnings: VecDeque<RoutingWarning<T>>,
+    /// drain this regularly for the extensions sent by neighbours
+    #[cfg_attr(feature = "serde", serde(skip_serializing, skip_deserializing))]
+    pub extensions: VecDeque<ReceivedExtension<T>>,
+    /// sources that need to be recomputed in the next call to update_routes
+    #[cfg_attr(feature = "serde", serde(skip_serializing, skip_deserializing))]
+    dirty_sources: RootSet<T::NodeAddress>,
+    /// next hop and metric of each link, as seen by the last call to update_routes
+    #[cfg_attr(feature = "serde", serde(skip_serializing, skip_deserializing))]
+    link_state: RootMap<T::Link, (T::NodeAddress, u16)>,
+    /// recompute every source in the next call to update_routes
+    #[cfg_attr(feature = "serde", serde(skip_serializing, skip_deserializing))]
+    dirty_all: bool,
+    /// number of full updates so far
+    #[cfg_attr(feature = "serde", serde(skip_serializing, skip_deserializing))]
+    rounds: u64,
+    /// Source -> (Link, round), a route that is waiting to replace the selected route, and the round in which it was first seen
+    #[cfg_attr(feature = "serde", serde(skip_serializing, skip_deserializing))]
+    switch_candidates: RootMap<T::NodeAddress, (T::Link, u64)>,
+    /// Source -> (penalty, suppressed), the instability of each destination, see [RoutingSystem::FLAP_PENALTY]
+    #[cfg_attr(feature = "serde", serde(skip_serializing, skip_deserializing))]
+    flap_penalties: RootMap<T::NodeAddress, (f64, bool)>,
+    /// Class -> the routing topology of a traffic class, with its own link metrics and routes over the links of this router.
+    /// This router is class 0, see [Router::add_class]
+    #[cfg_attr(feature = "serde", serde(skip_serializing, skip_deserializing))]
+    pub classes: RootMap<u8, Router<T>>,
+    /// the nonce of the next ack request
+    #[cfg_attr(feature = "serde", serde(skip_serializing, skip_deserializing))]
+    next_nonce: u32,
+    /// the urgent updates over each link that were not acknowledged yet, see [RoutingSystem::REQUEST_ACKS]
+    #[cfg_attr(feature = "serde", serde(skip_serializing, skip_deserializing))]
+    unacknowledged: RootMap<T::Link, PendingAcks<T>>,
+    /// nodes whose attributes changed, and have to be sent to neighbours in the next update
+    #[cfg_attr(feature = "serde", serde(skip_serializing, skip_deserializing))]
+    broadcast_attributes_for: RootSet<T::NodeAddress>,
+    /// Link -> (neighbour, metric), the links that are up, as last flooded with [RoutingSystem::LINK_STATE]
+    #[cfg_attr(feature = "serde", serde(skip_serializing, skip_deserializing))]
+    advertised_links: RootMap<T::Link, (T::NodeAddress, u16)>,
+    /// nodes whose link state changed, and has to be sent to neighbours in the next update
+    #[cfg_attr(feature = "serde", serde(skip_serializing, skip_deserializing))]
+    broadcast_link_state_for: RootSet<T::NodeAddress>,
+    /// Destination -> round, the route discoveries that were sent or forwarded, and are waiting for an answer
+    #[cfg_attr(feature = "serde", serde(skip_serializing, skip_deserializing))]
+    discoveries: RootMap<T::NodeAddress, u64>,
+    /// Destination -> round, the last round in which an on-demand route was requested
+    #[cfg_attr(feature = "serde", serde(skip_serializing, skip_deserializing))]
+    route_usage: RootMap<T::NodeAddress, u64>,
+}
+
+/// Urgent updates sent over a link, waiting to be acknowledged
+#[derive(Educe)]
+#[educe(Default(bound()))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(bound = ""), serde_as)]
+struct PendingAcks<T: RoutingSystem + ?Sized> {
+    /// Source -> (nonce, update), the last update for each source
+    #[cfg_attr(feature = "serde", serde_as(as = "Vec<(_, _)>"))]
+    updates: RootMap<T::NodeAddress, (u32, RouteUpdate<T>)>,
+    /// number of retransmissions since the neighbour last acknowledged an update
+    retransmissions: u32,
 }
 
 #[derive(Eq, PartialEq)]
@@ -56,15 +146,40 @@ impl<T: RoutingSystem> Router<T> {
     }
     pub fn new(address: T::NodeAddress) -> Self {
         Self{
-            links: HashMap::new(),
-            routes: HashMap::new(),
+            links: RootMap::new(),
+            routes: RootMap::new(),
             address,
-            seqno_requests: HashMap::new(),
-            broadcast_route_for: HashSet::new(),
+            seqno_requests: RootMap::new(),
+            broadcast_route_for: RootSet::new(),
+            proactive: RootSet::new(),
             outbound_packets: Vec::new(),
             seqno: 0,
+            area_seqno: 0,
+            link_state_seqno: 0,
+            anycast: RootMap::new(),
+            static_routes: RootMap::new(),
+            attributes: BTreeMap::new(),
+            announces_attributes: false,
+            node_attributes: RootMap::new(),
+            topology: RootMap::new(),
             mac_sys: Default::default(),
-            warnings: Default::default()
+            route_selector: Default::default(),
+            warnings: Default::default(),
+            extensions: Default::default(),
+            dirty_sources: RootSet::new(),
+            link_state: RootMap::new(),
+            dirty_all: false,
+            rounds: 0,
+            switch_candidates: RootMap::new(),
+            flap_penalties: RootMap::new(),
+            classes: RootMap::new(),
+            next_nonce: 0,
+            unacknowledged: RootMap::new(),
+            broadcast_attributes_for: RootSet::new(),
+            advertised_links: RootMap::new(),
+            broadcast_link_state_for: RootSet::new(),
+            discoveries: RootMap::new(),
+            route_usage: RootMap::new(),
         }
     }
 
@@ -76,20 +191,291 @@ impl<T: RoutingSystem> Router<T> {
     
     /// updates the state of the router, does not broadcast routes
     pub fn update(&mut self){
+        self.write_hellos(false);
+        self.check_link_state(false);
         self.update_routes();
+        self.activate_static_routes();
+        self.answer_discoveries();
         self.broadcast_seqno_updates();
+        self.broadcast_attributes(false);
+        self.broadcast_link_states(false);
+        self.update_classes(false);
     }
     /// performs a full update on the state of the router, will broadcast routes to neighbours
     pub fn full_update(&mut self){
+        self.write_hellos(true);
+        self.forget_lost_seqno_requests();
+        // routes waiting to be switched to may be selected this round, even if nothing else changed
+        self.rounds += 1;
+        self.dirty_sources.extend(self.switch_candidates.keys().cloned());
+        self.decay_flap_penalties();
+        self.expire_routes();
+        self.check_link_state(true);
         self.update_routes();
+        self.activate_static_routes();
+        self.answer_discoveries();
+        self.expire_attributes();
+        self.expire_link_states();
 
         self.solve_starvation();
         self.broadcast_routes();
 
         self.broadcast_seqno_updates();
+        self.broadcast_attributes(true);
+        self.broadcast_link_states(true);
+        self.update_classes(true);
     }
 
     // region Interface
+    /// The packets written since the last call to [Router::take_outbound_packets], as they were queued.
+    /// Send them with take_outbound_packets, which coalesces urgent updates, requests acks and downgrades packets for old neighbours.
+    pub fn outbound_packets(&self) -> &[OutboundPacket<T>] {
+        &self.outbound_packets
+    }
+
+    /// Drains the outbound packet queue, in the order the packets should be sent.
+    ///
+    /// An urgent update that is superseded by a later one for the same source over the same link is dropped,
+    /// and the urgent updates over each link are merged into a single packet. Urgent packets are sent before periodic ones,
+    /// see [PacketPriority].
+    ///
+    /// With [RoutingSystem::REQUEST_ACKS], urgent updates are sent as [Packet::AckRequest], and kept until they are
+    /// acknowledged, or superseded by a later update or full-table update over the same link.
+    pub fn take_outbound_packets(&mut self) -> Vec<OutboundPacket<T>> {
+        enum Queued<T: RoutingSystem + ?Sized> {
+            Packet(OutboundPacket<T>),
+            /// the urgent updates over a link, and the position in the outbound queue of each update
+            Urgent(T::Link, T::NodeAddress, Vec<(RouteUpdate<T>, usize)>),
+        }
+        let mut queue = Vec::new();
+        // Link -> index of the urgent updates over the link in the queue
+        let mut urgent: RootMap<T::Link, usize> = RootMap::new();
+        // Link -> position in the outbound queue of the last full-table update over the link
+        let mut batches: RootMap<T::Link, usize> = RootMap::new();
+        for (pos, packet) in std::mem::take(&mut self.outbound_packets).into_iter().enumerate() {
+            let updates = match packet.packet.data() {
+                Packet::UrgentRouteUpdate(update) => vec![update.clone()],
+                Packet::MergedRouteUpdate { routes } => routes.clone(),
+                Packet::BatchRouteUpdate { .. } => {
+                    batches.insert(packet.link.clone(), pos);
+                    queue.push(Queued::Packet(packet));
+                    continue;
+                }
+                _ => {
+                    queue.push(Queued::Packet(packet));
+                    continue;
+                }
+            };
+            let idx = *urgent.entry(packet.link.clone()).or_insert_with(|| {
+                queue.push(Queued::Urgent(packet.link.clone(), packet.dest.clone(), Vec::new()));
+                queue.len() - 1
+            });
+            let Queued::Urgent(_, _, queued) = &mut queue[idx] else {
+                unreachable!()
+            };
+            for update in updates {
+                let addr = &update.source.data().addr;
+                match queued.iter_mut().find(|(x, _)| x.source.data().addr == *addr) {
+                    Some(superseded) => *superseded = (update, pos),
+                    None => queued.push((update, pos)),
+                }
+            }
+        }
+        if T::REQUEST_ACKS {
+            // a full-table update supersedes the urgent updates that were sent before it
+            for link in batches.keys() {
+                if let Some(pending) = self.unacknowledged.get_mut(link) {
+                    pending.updates.clear();
+                }
+            }
+        }
+        let mut packets = Vec::new();
+        for queued in queue {
+            let (link, dest, updates) = match queued {
+                Queued::Packet(packet) => {
+                    packets.extend(self.downgrade(packet));
+                    continue;
+                }
+                Queued::Urgent(link, dest, updates) => (link, dest, updates),
+            };
+            let (version, capabilities) = self.links.get(&link).map_or((0, Capabilities::NONE), |neigh| (neigh.version(), neigh.capabilities()));
+            let updates = if version == 0 {
+                updates.into_iter().filter_map(|(update, pos)| Some((Self::downgrade_route(update)?, pos))).collect()
+            } else {
+                updates
+            };
+            let groups = if capabilities.contains(Capabilities::MERGED_UPDATES) {
+                vec![updates]
+            } else {
+                updates.into_iter().map(|update| vec![update]).collect()
+            };
+            for updates in groups {
+                let mut routes = Vec::new();
+                let mut acked = Vec::new();
+                for (update, pos) in updates {
+                    if batches.get(&link).is_none_or(|batch| *batch < pos) {
+                        acked.push(update.clone());
+                    }
+                    routes.push(update);
+                }
+                let mut packet = if routes.len() == 1 {
+                    Packet::UrgentRouteUpdate(routes.pop().unwrap())
+                } else {
+                    Packet::MergedRouteUpdate { routes }
+                };
+                if T::REQUEST_ACKS && capabilities.contains(Capabilities::ACKS) {
+                    let nonce = self.next_nonce;
+                    self.next_nonce = self.next_nonce.wrapping_add(1);
+                    let pending = self.unacknowledged.entry(link.clone()).or_default();
+                    for update in acked {
+                        pending.updates.insert(update.source.data().addr.clone(), (nonce, update));
+                    }
+                    packet = Packet::AckRequest {
+                        nonce,
+                        packet: Box::new(self.mac_sys.sign(packet, self)),
+                    };
+                }
+                packets.push(OutboundPacket {
+                    link: link.clone(),
+                    dest: dest.clone(),
+                    packet: self.mac_sys.sign(packet, self),
+                });
+            }
+        }
+        packets.sort_by_key(|packet| packet.priority());
+        packets
+    }
+
+    /// rewrites a packet for the protocol spoken by its destination, returns None if it cannot be sent
+    fn downgrade(&self, packet: OutboundPacket<T>) -> Option<OutboundPacket<T>> {
+        let (version, capabilities) = self.links.get(&packet.link).map_or((0, Capabilities::NONE), |neigh| (neigh.version(), neigh.capabilities()));
+        match packet.packet.data() {
+            Packet::BatchRouteUpdate { routes } if version == 0 => {
+                let routes = routes.iter().cloned().filter_map(Self::downgrade_route).collect();
+                let data = self.mac_sys.sign(Packet::BatchRouteUpdate { routes }, self);
+                Some(OutboundPacket { packet: data, ..packet })
+            }
+            Packet::ClassPacket { .. } if !capabilities.contains(Capabilities::CLASSES) => None,
+            Packet::Extension { .. } if !capabilities.contains(Capabilities::EXTENSIONS) => None,
+            Packet::AttributeUpdate { .. } if !capabilities.contains(Capabilities::ATTRIBUTES) => None,
+            Packet::LinkStateUpdate { .. } if !capabilities.contains(Capabilities::LINK_STATE) => None,
+            Packet::AckRequest { packet: inner, .. } if !capabilities.contains(Capabilities::ACKS) => {
+                self.downgrade(OutboundPacket {
+                    link: packet.link.clone(),
+                    dest: packet.dest.clone(),
+                    packet: (**inner).clone(),
+                })
+            }
+            _ => Some(packet),
+        }
+    }
+
+    /// rewrites a route update for a version 0 peer, or a peer that has not answered a hello yet. The update is sent without
+    /// a hop count, and injected routes are not sent at all, so that the update serializes as it did in version 0,
+    /// and the peer can validate its signatures
+    fn downgrade_route(update: RouteUpdate<T>) -> Option<RouteUpdate<T>> {
+        if update.source.data().injected {
+            return None;
+        }
+        Some(RouteUpdate {
+            hop_count: 0,
+            ..update
+        })
+    }
+
+    /// sends a hello to the neighbours that have not answered one, only to new neighbours unless retrying
+    fn write_hellos(&mut self, retry: bool) {
+        let mut hellos = Vec::new();
+        for (link, neigh) in &mut self.links {
+            if let NeighbourProtocol::Unknown { hellos_sent } = &mut neigh.protocol {
+                if *hellos_sent < T::HELLO_ATTEMPTS && (retry || *hellos_sent == 0) {
+                    *hellos_sent += 1;
+                    hellos.push((link.clone(), neigh.addr.clone()));
+                }
+            }
+        }
+        for (link, dest) in hellos {
+            self.write_hello(link, dest, false);
+        }
+    }
+
+    fn write_hello(&mut self, link: T::Link, dest: T::NodeAddress, answer: bool) {
+        let packet = self.mac_sys.sign(
+            Packet::Hello {
+                version: PROTOCOL_VERSION,
+                capabilities: Capabilities::ALL,
+                answer,
+            },
+            self,
+        );
+        self.outbound_packets.push(OutboundPacket { link, dest, packet });
+    }
+
+    /// Resends the urgent updates that were not acknowledged, call this on a timer that is shorter than the interval between full updates.
+    /// Updates sent over links that are down are dropped.
+    /// A neighbour that does not acknowledge any of [RoutingSystem::MAX_RETRANSMISSIONS] retransmissions is reported with
+    /// [RoutingWarning::NotAcknowledging], and its updates are dropped. Does nothing without [RoutingSystem::REQUEST_ACKS].
+    pub fn retransmit(&mut self) {
+        // there is no point in resending updates over links that are down
+        let links = &self.links;
+        self.unacknowledged.retain(|link, _| links.get(link).is_some_and(|neigh| neigh.metric != INF));
+        let mut warnings = Vec::new();
+        let mut resend = Vec::new();
+        for (link, pending) in &mut self.unacknowledged {
+            if pending.updates.is_empty() {
+                continue;
+            }
+            if pending.retransmissions >= T::MAX_RETRANSMISSIONS {
+                pending.updates.clear();
+                if pending.retransmissions == T::MAX_RETRANSMISSIONS {
+                    // only report the neighbour once, until it acknowledges again
+                    pending.retransmissions += 1;
+                    warnings.push(NotAcknowledging {
+                        link: link.clone(),
+                        neighbour: self.links[link].addr.clone(),
+                    });
+                }
+                continue;
+            }
+            pending.retransmissions += 1;
+            for (_, update) in pending.updates.values() {
+                resend.push((link.clone(), update.clone()));
+            }
+        }
+        for (link, update) in resend {
+            self.outbound_packets.push(OutboundPacket {
+                dest: self.links[&link].addr.clone(),
+                link,
+                packet: self.mac_sys.sign(Packet::UrgentRouteUpdate(update), self),
+            });
+        }
+        for warning in warnings {
+            self.warn(warning);
+        }
+        if !self.classes.is_empty() {
+            for router in self.classes.values_mut() {
+                router.retransmit();
+            }
+            self.collect_class_output();
+        }
+    }
+
+    /// sends application data to the neighbour on a link, it is dropped if the neighbour does not support extensions
+    pub fn write_extension(&mut self, link: &T::Link, data: T::Extension) {
+        if let Some(neigh) = self.links.get(link) {
+            self.outbound_packets.push(OutboundPacket {
+                link: link.clone(),
+                dest: neigh.addr.clone(),
+                packet: self.mac_sys.sign(Packet::Extension { data }, self),
+            });
+        }
+    }
+
+    /// sends application data to all neighbours
+    pub fn broadcast_extension(&mut self, data: T::Extension) {
+        self.write_broadcast_packet(&self.mac_sys.sign(Packet::Extension { data }, self));
+    }
+
     /// writes a packet to the outbound packet queue for all neighbours
     pub fn write_broadcast_packet(&mut self, packet: &MAC<Packet<T>, T>) {
         // send to all neighbours
@@ -104,13 +490,655 @@ impl<T: RoutingSystem> Router<T> {
     
     // endregion
 
+    // region Attributes
+
+    /// the attributes of this node
+    pub fn attributes(&self) -> &BTreeMap<String, String> {
+        &self.attributes
+    }
+
+    /// Replaces the attributes of this node, and sends them to the network in the next update.
+    /// The seqno is incremented, so that the new attributes replace the old ones on every node.
+    pub fn set_attributes(&mut self, attributes: BTreeMap<String, String>) {
+        if attributes == self.attributes {
+            return;
+        }
+        self.attributes = attributes;
+        self.announces_attributes = true;
+        increment(&mut self.seqno);
+        self.broadcast_route_for.insert(self.address.clone());
+        self.broadcast_attributes_for.insert(self.address.clone());
+    }
+
+    /// the attributes of a node, or None if they are not known
+    pub fn attributes_of(&self, addr: &T::NodeAddress) -> Option<&BTreeMap<String, String>> {
+        if *addr == self.address {
+            return Some(&self.attributes);
+        }
+        self.node_attributes.get(addr).map(|attributes| &attributes.data().attributes)
+    }
+
+    /// sends the attributes that changed to all neighbours, or every known attribute if full
+    fn broadcast_attributes(&mut self, full: bool) {
+        let pending = std::mem::take(&mut self.broadcast_attributes_for);
+        let mut attributes = Vec::new();
+        // a node that never had attributes has nothing to announce, but a cleared set must keep replacing the old one where it was lost
+        if pending.contains(&self.address) || (full && self.announces_attributes) {
+            attributes.push(self.mac_sys.sign(
+                NodeAttributes {
+                    source: Source {
+                        addr: self.address.clone(),
+                        seqno: self.seqno,
+                        injected: false,
+                    },
+                    attributes: self.attributes.clone(),
+                },
+                self,
+            ));
+        }
+        // an update only looks up the nodes whose attributes changed, rather than going over every known node
+        if full {
+            attributes.extend(self.node_attributes.values().cloned());
+        } else {
+            attributes.extend(pending.iter().filter_map(|addr| self.node_attributes.get(addr)).cloned());
+        }
+        if !attributes.is_empty() {
+            self.write_broadcast_packet(&self.mac_sys.sign(Packet::AttributeUpdate { attributes }, self));
+        }
+    }
+
+    /// forgets the attributes of nodes that are no longer reachable
+    fn expire_attributes(&mut self) {
+        Self::retain_reachable(&self.routes, &mut self.node_attributes);
+    }
+
+    fn handle_attribute_update(
+        &mut self,
+        attributes: &[MAC<NodeAttributes<T>, T>],
+        link: &T::Link
+    ) -> Result<(), RoutingError<T>> {
+        for node in attributes {
+            let Source { addr, seqno, .. } = &node.data().source;
+            if *addr == self.address {
+                continue;
+            }
+            if !self.mac_sys.validate(node, addr) {
+                return Err(MACValidationFail {
+                    link: link.clone()
+                });
+            }
+            // attributes are replaced only by the attributes of a newer seqno
+            if self.node_attributes.get(addr).is_some_and(|cur| !seqno_less_than(cur.data().source.seqno, *seqno)) {
+                continue;
+            }
+            self.node_attributes.insert(addr.clone(), node.clone());
+            self.broadcast_attributes_for.insert(addr.clone());
+        }
+        Ok(())
+    }
+
+    /// removes the entries of nodes that we have no reachable route to
+    fn retain_reachable<V>(routes: &RootMap<T::NodeAddress, Route<T>>, store: &mut RootMap<T::NodeAddress, V>) {
+        store.retain(|addr, _| routes.get(addr).is_some_and(|route| route.metric != INF));
+    }
+
+    // endregion
+
+    // region On-demand routes
+
+    /// Returns the route to a destination, and keeps it from expiring, call this whenever a packet is sent or forwarded to it.
+    /// With [RoutingSystem::ON_DEMAND], a destination without a reachable route is discovered, by flooding a seqno request for it.
+    /// The route is available after the destination or a router with a route to it answers.
+    pub fn request_route(&mut self, dest: &T::NodeAddress) -> Option<&Route<T>> {
+        if *dest == self.address {
+            return None;
+        }
+        // a route that is discovered later starts with a full lease, see expire_routes
+        if self.routes.contains_key(dest) {
+            self.route_usage.insert(dest.clone(), self.rounds);
+        }
+        let reachable = self.routes.get(dest).is_some_and(|route| route.metric != INF);
+        if T::ON_DEMAND && !reachable && !self.discoveries.contains_key(dest) {
+            self.discoveries.insert(dest.clone(), self.rounds);
+            let seqno = self.get_seqno_for(dest).map_or(0, |seqno| increment_by(seqno, 1));
+            self.write_broadcast_packet(&self.mac_sys.sign(
+                Packet::SeqnoRequest {
+                    source: dest.clone(),
+                    seqno,
+                },
+                self,
+            ));
+        }
+        self.routes.get(dest)
+    }
+
+    /// sends the routes that were discovered towards the routers that asked for them
+    fn answer_discoveries(&mut self) {
+        let rounds = self.rounds;
+        let routes = &self.routes;
+        let mut answered = Vec::new();
+        self.discoveries.retain(|dest, since| {
+            if routes.get(dest).is_some_and(|route| route.metric != INF) {
+                answered.push(dest.clone());
+                return false;
+            }
+            rounds - *since < T::DISCOVERY_TIMEOUT_ROUNDS
+        });
+        for dest in answered {
+            // a discovered route lives for as long as a requested one
+            self.route_usage.insert(dest.clone(), rounds);
+            self.broadcast_route_for.insert(dest);
+        }
+    }
+
+    /// retracts the on-demand routes that were not requested for [RoutingSystem::ROUTE_EXPIRY_ROUNDS].
+    /// An expired route is kept as a retracted entry with its seqno and fd, so that stale routes through us stay infeasible
+    /// until the destination is discovered with a newer seqno
+    fn expire_routes(&mut self) {
+        if !T::ON_DEMAND {
+            return;
+        }
+        let mut expired = Vec::new();
+        for (src, route) in &self.routes {
+            if self.proactive.contains(src) || self.is_expired(src, route) {
+                continue;
+            }
+            // routes that were never requested live as long as requested ones
+            let used = *self.route_usage.entry(src.clone()).or_insert(self.rounds);
+            if self.rounds - used >= T::ROUTE_EXPIRY_ROUNDS {
+                expired.push(src.clone());
+            }
+        }
+        for src in expired {
+            self.route_usage.remove(&src);
+            self.switch_candidates.remove(&src);
+            self.seqno_requests.remove(&src);
+            for neigh in self.links.values_mut() {
+                neigh.routes.remove(&src);
+            }
+            let route = self.routes.get_mut(&src).unwrap();
+            route.metric = INF;
+            if !std::mem::replace(&mut route.retracted, true) {
+                let source = route.source.clone();
+                self.write_retraction_for(source);
+            }
+        }
+    }
+
+    /// whether the route is an expired on-demand route, which is kept only for its seqno and fd
+    fn is_expired(&self, src: &T::NodeAddress, route: &Route<T>) -> bool {
+        T::ON_DEMAND && route.retracted && !self.proactive.contains(src) && !self.route_usage.contains_key(src)
+    }
+
+    // endregion
+
+    // region Anycast
+
+    /// Starts originating routes to an anycast address, which several nodes can serve. Every node reaches the closest of them.
+    /// The origins share the seqno of the address: each origin follows the highest seqno it hears from the others,
+    /// so that a seqno request can be answered by any of them. The MAC system must accept the signatures of every origin.
+    pub fn serve_anycast(&mut self, addr: T::NodeAddress) {
+        if self.anycast.contains_key(&addr) || addr == self.address {
+            return;
+        }
+        // start above the seqno we have seen, so that our routes are not ignored
+        let seqno = self.get_seqno_for(&addr).map_or(0, |seqno| increment_by(seqno, 1));
+        self.forget_routes_to(&addr);
+        self.anycast.insert(addr.clone(), seqno);
+        self.broadcast_route_for.insert(addr);
+    }
+
+    /// Stops originating routes to an anycast address, the route through us is retracted so that neighbours switch to another origin
+    pub fn stop_anycast(&mut self, addr: &T::NodeAddress) {
+        if let Some(seqno) = self.anycast.remove(addr) {
+            let update = self.origin_update(addr, seqno, 0);
+            self.retract_origin(update);
+        }
+    }
+
+    /// Retracts a route to an address that we stop originating. A retracted route with the seqno and metric that we advertised is kept
+    /// as its feasibility distance, so that the routes through us that neighbours still advertise stay infeasible until the seqno increases
+    fn retract_origin(&mut self, update: RouteUpdate<T>) {
+        let addr = update.source.data().addr.clone();
+        self.write_retraction_for(update.source.clone());
+        // the route is never used, but it needs a link, a link that is removed later only retracts it again
+        let Some((link, neigh)) = self.links.iter().next() else {
+            return;
+        };
+        self.routes.insert(addr.clone(), Route {
+            source: update.source,
+            metric: INF,
+            fd: update.metric,
+            link: link.clone(),
+            next_hop: neigh.addr.clone(),
+            hop_count: 0,
+            retracted: true,
+        });
+        self.dirty_sources.insert(addr);
+    }
+
+    /// removes the routes to an address that we start to originate
+    fn forget_routes_to(&mut self, addr: &T::NodeAddress) {
+        self.routes.remove(addr);
+        self.switch_candidates.remove(addr);
+        for neigh in self.links.values_mut() {
+            neigh.routes.remove(addr);
+        }
+    }
+
+    /// a route to an address that we originate besides our own
+    fn origin_update(&self, addr: &T::NodeAddress, seqno: u16, metric: u16) -> RouteUpdate<T> {
+        RouteUpdate {
+            source: self.mac_sys.sign(
+                Source {
+                    addr: addr.clone(),
+                    seqno,
+                    injected: false,
+                },
+                self,
+            ),
+            metric,
+            hop_count: 0,
+        }
+    }
+
+    // endregion
+
+    // region Static routes
+
+    /// Injects a route to an address, which is advertised with the given metric like our own address.
+    /// If the distance is lower than [RoutingSystem::LEARNED_DISTANCE], the route replaces any learned route to the address.
+    /// Otherwise, it is only used while no learned route is reachable. Several routers can inject the same address, as with anycast.
+    ///
+    /// A preferred route to the address of a node that is reachable is only used by this router, and is not advertised,
+    /// so that the node keeps its address. See [Router::static_route_to].
+    ///
+    /// The advertised route is signed by this router and validated against the injected address, like an anycast address,
+    /// so the MAC system must accept the signature of this router for that address. Otherwise, the other routers reject the
+    /// whole packet that carries the route.
+    pub fn add_static_route(&mut self, addr: T::NodeAddress, metric: u16, distance: u8) {
+        if addr == self.address || self.anycast.contains_key(&addr) {
+            return;
+        }
+        self.remove_static_route(&addr);
+        self.static_routes.insert(addr, StaticRoute {
+            metric,
+            distance,
+            seqno: 0,
+            active: false,
+            advertised: false,
+        });
+        self.activate_static_routes();
+    }
+
+    /// Removes an injected route, it is retracted if it was advertised, see [Router::stop_anycast]
+    pub fn remove_static_route(&mut self, addr: &T::NodeAddress) {
+        if let Some(route) = self.static_routes.remove(addr) {
+            if route.advertised {
+                self.retract_origin(self.injected_update(addr, &route));
+            }
+        }
+    }
+
+    /// The injected route to dest, if this router uses it. It takes precedence over the learned route in [Router::route_to]
+    pub fn static_route_to(&self, dest: &T::NodeAddress) -> Option<&StaticRoute> {
+        self.static_routes.get(dest).filter(|route| route.active)
+    }
+
+    /// activates the preferred static routes, and the others that have no reachable learned route.
+    /// Active routes are advertised unless the node that owns the address is reachable
+    fn activate_static_routes(&mut self) {
+        let mut advertised = Vec::new();
+        for (addr, route) in &mut self.static_routes {
+            let learned = self.routes.get(addr).filter(|route| route.metric != INF);
+            if !route.active && (route.distance < T::LEARNED_DISTANCE || learned.is_none()) {
+                route.active = true;
+            }
+            // a learned route that was not injected leads to the node that owns the address
+            let owned = learned.is_some_and(|route| !route.source.data().injected);
+            if route.active && !route.advertised && !owned {
+                advertised.push(addr.clone());
+            }
+        }
+        for addr in advertised {
+            // start above the seqno we have seen, so that our route is not ignored
+            let seqno = self.get_seqno_for(&addr).map_or(0, |seqno| increment_by(seqno, 1));
+            self.forget_routes_to(&addr);
+            let route = self.static_routes.get_mut(&addr).unwrap();
+            route.seqno = seqno;
+            route.advertised = true;
+            self.broadcast_route_for.insert(addr);
+        }
+    }
+
+    /// the route that we originate for an injected address
+    fn injected_update(&self, addr: &T::NodeAddress, route: &StaticRoute) -> RouteUpdate<T> {
+        RouteUpdate {
+            source: self.mac_sys.sign(
+                Source {
+                    addr: addr.clone(),
+                    seqno: route.seqno,
+                    injected: true,
+                },
+                self,
+            ),
+            metric: route.metric,
+            hop_count: 0,
+        }
+    }
+
+    // endregion
+
+    // region Areas
+
+    /// the area of this router, see [RoutingSystem::area_of]
+    pub fn area(&self) -> Option<T::NodeAddress> {
+        T::area_of(&self.address)
+    }
+
+    /// whether this router has a working link to a router of another area, and advertises the summary of its area to it
+    pub fn is_border(&self) -> bool {
+        let Some(area) = self.area() else {
+            return false;
+        };
+        self.links.values().any(|neigh| neigh.metric != INF && T::area_of(&neigh.addr).as_ref() != Some(&area))
+    }
+
+    /// the route to a node of our area, or to the area of any other node. An active static route to dest takes precedence, see [Router::static_route_to]
+    pub fn route_to(&self, dest: &T::NodeAddress) -> Option<&Route<T>> {
+        match T::area_of(dest) {
+            Some(area) if Some(&area) != self.area().as_ref() => self.routes.get(&area),
+            _ => self.routes.get(dest),
+        }
+    }
+
+    /// whether we keep routes to a source, which is either a node of our area, or outside of every area
+    fn accepts_source(&self, src: &T::NodeAddress) -> bool {
+        let area = self.area();
+        match T::area_of(src) {
+            Some(src_area) => Some(src_area) == area,
+            None => Some(src) != area.as_ref(),
+        }
+    }
+
+    /// the summary route of our area, which we originate as a border router
+    fn summary_update(&self) -> RouteUpdate<T> {
+        self.origin_update(&self.area().unwrap(), self.area_seqno, 0)
+    }
+
+    /// sends a full update to each neighbour, routes inside our area are replaced by its summary for neighbours of other areas
+    fn write_border_routes(&mut self, routes: Vec<RouteUpdate<T>>) {
+        let area = self.area();
+        let mut outside: Vec<_> = routes.iter()
+            .filter(|update| T::area_of(&update.source.data().addr).is_none())
+            .cloned()
+            .collect();
+        outside.push(self.summary_update());
+        let inside = self.mac_sys.sign(Packet::BatchRouteUpdate { routes }, self);
+        let outside = self.mac_sys.sign(Packet::BatchRouteUpdate { routes: outside }, self);
+        for (link, neigh) in &self.links {
+            let packet = if T::area_of(&neigh.addr) == area { &inside } else { &outside };
+            self.outbound_packets.push(OutboundPacket {
+                link: link.clone(),
+                dest: neigh.addr.clone(),
+                packet: packet.clone(),
+            });
+        }
+    }
+
+    // endregion
+
+    // region Link state
+
+    /// every link in the network that is known, as (from, to, metric), with [RoutingSystem::LINK_STATE]
+    pub fn topology_edges(&self) -> Vec<(T::NodeAddress, T::NodeAddress, u16)> {
+        let mut edges = Vec::new();
+        for (addr, metric) in self.advertised_links.values() {
+            edges.push((self.address.clone(), addr.clone(), *metric));
+        }
+        for (from, state) in &self.topology {
+            for (addr, metric) in &state.data().neighbours {
+                edges.push((from.clone(), addr.clone(), *metric));
+            }
+        }
+        edges
+    }
+
+    /// increments the link state seqno if a link came up or went down since the link state was last flooded, or if full and a metric changed
+    fn check_link_state(&mut self, full: bool) {
+        if !T::LINK_STATE {
+            return;
+        }
+        let links: RootMap<_, _> = self.links.iter()
+            .filter(|(_, neigh)| neigh.metric != INF)
+            .map(|(link, neigh)| (link.clone(), (neigh.addr.clone(), neigh.metric)))
+            .collect();
+        // metrics of live links jitter, so their changes wait for the next full update, rather than flooding the network on every update
+        let same_links = links.len() == self.advertised_links.len()
+            && links.iter().all(|(link, (addr, _))| self.advertised_links.get(link).is_some_and(|(cur, _)| cur == addr));
+        if !full && same_links {
+            return;
+        }
+        if links != self.advertised_links {
+            self.advertised_links = links;
+            increment(&mut self.link_state_seqno);
+            self.broadcast_link_state_for.insert(self.address.clone());
+        }
+    }
+
+    /// sends the link states that changed to all neighbours, or every known link state if full
+    fn broadcast_link_states(&mut self, full: bool) {
+        let pending = std::mem::take(&mut self.broadcast_link_state_for);
+        if !T::LINK_STATE {
+            return;
+        }
+        let mut states = Vec::new();
+        if full || pending.contains(&self.address) {
+            states.push(self.mac_sys.sign(
+                LinkState {
+                    source: Source {
+                        addr: self.address.clone(),
+                        seqno: self.link_state_seqno,
+                        injected: false,
+                    },
+                    neighbours: self.advertised_links.values().cloned().collect(),
+                },
+                self,
+            ));
+        }
+        if full {
+            states.extend(self.topology.values().cloned());
+        } else {
+            states.extend(pending.iter().filter_map(|addr| self.topology.get(addr)).cloned());
+        }
+        if !states.is_empty() {
+            self.write_broadcast_packet(&self.mac_sys.sign(Packet::LinkStateUpdate { states }, self));
+        }
+    }
+
+    /// forgets the link states of nodes that are no longer reachable
+    fn expire_link_states(&mut self) {
+        Self::retain_reachable(&self.routes, &mut self.topology);
+    }
+
+    fn handle_link_state_update(
+        &mut self,
+        states: &[MAC<LinkState<T>, T>],
+        link: &T::Link
+    ) -> Result<(), RoutingError<T>> {
+        if !T::LINK_STATE {
+            return Ok(());
+        }
+        for state in states {
+            let Source { addr, seqno, .. } = &state.data().source;
+            if !self.mac_sys.validate(state, addr) {
+                return Err(MACValidationFail {
+                    link: link.clone()
+                });
+            }
+            if *addr == self.address {
+                // our link state from before a restart is still flooded, replace it with a newer one
+                if seqno_less_than(self.link_state_seqno, *seqno) {
+                    self.link_state_seqno = *seqno;
+                    increment(&mut self.link_state_seqno);
+                    self.broadcast_link_state_for.insert(self.address.clone());
+                }
+                continue;
+            }
+            if self.topology.get(addr).is_some_and(|cur| !seqno_less_than(cur.data().source.seqno, *seqno)) {
+                continue;
+            }
+            self.topology.insert(addr.clone(), state.clone());
+            self.broadcast_link_state_for.insert(addr.clone());
+        }
+        Ok(())
+    }
+
+    // endregion
+
+    // region Traffic classes
+
+    /// Adds a traffic class, which is routed separately over the links of this router, and returns its router.
+    /// Links are unusable in the class until they are given a metric with [Router::set_class_link_metric].
+    /// Neighbours only exchange routes for the classes that both of them have added.
+    pub fn add_class(&mut self, class: u8) -> &mut Router<T> {
+        if class == 0 {
+            return self;
+        }
+        if !self.classes.contains_key(&class) {
+            let mut router = Router::new(self.address.clone());
+            router.seqno = self.seqno;
+            self.classes.insert(class, router);
+            self.sync_class_links();
+        }
+        self.classes.get_mut(&class).unwrap()
+    }
+
+    /// the router of a traffic class, class 0 is this router
+    pub fn class(&self, class: u8) -> Option<&Router<T>> {
+        if class == 0 {
+            Some(self)
+        } else {
+            self.classes.get(&class)
+        }
+    }
+
+    pub fn class_mut(&mut self, class: u8) -> Option<&mut Router<T>> {
+        if class == 0 {
+            Some(self)
+        } else {
+            self.classes.get_mut(&class)
+        }
+    }
+
+    /// the route to a destination, in a traffic class
+    pub fn route_for(&self, dest: &T::NodeAddress, class: u8) -> Option<&Route<T>> {
+        self.class(class)?.routes.get(dest)
+    }
+
+    pub fn set_class_link_metric(&mut self, class: u8, link: &T::Link, new_metric: u16) {
+        self.sync_class_links();
+        if let Some(router) = self.class_mut(class) {
+            router.set_link_metric(link, new_metric);
+        }
+    }
+
+    /// mirrors the links of this router in every class, links that are new to a class are unusable
+    fn sync_class_links(&mut self) {
+        let links = &self.links;
+        for router in self.classes.values_mut() {
+            router.links.retain(|link, neigh| links.get(link).is_some_and(|x| x.addr == neigh.addr));
+            for (link, neigh) in links {
+                let neighbour = router.links.entry(link.clone()).or_insert_with(|| {
+                    let mut neighbour = Neighbour::new(neigh.addr.clone());
+                    neighbour.metric = INF;
+                    neighbour
+                });
+                // hellos are only exchanged in the default class
+                neighbour.protocol = neigh.protocol.clone();
+            }
+            router.proactive.clone_from(&self.proactive);
+        }
+    }
+
+    /// every class shares our seqno, as a seqno request may be answered by any of them
+    fn sync_class_seqno(&mut self) {
+        let mut seqno = self.seqno;
+        for router in self.classes.values() {
+            if seqno_less_than(seqno, router.seqno) {
+                seqno = router.seqno;
+            }
+        }
+        if seqno != self.seqno {
+            self.seqno = seqno;
+            self.broadcast_route_for.insert(self.address.clone());
+        }
+        for router in self.classes.values_mut() {
+            if router.seqno != seqno {
+                router.seqno = seqno;
+                router.broadcast_route_for.insert(router.address.clone());
+            }
+        }
+    }
+
+    /// moves the packets and warnings of every class to this router
+    fn collect_class_output(&mut self) {
+        let mut packets = Vec::new();
+        for (class, router) in &mut self.classes {
+            for packet in router.take_outbound_packets() {
+                packets.push((*class, packet));
+            }
+            self.warnings.extend(router.warnings.drain(..));
+        }
+        while self.warnings.len() > T::MAX_WARN_LENGTH {
+            self.warnings.pop_front();
+        }
+        for (class, OutboundPacket { link, dest, packet }) in packets {
+            let packet = self.mac_sys.sign(
+                Packet::ClassPacket {
+                    class,
+                    packet: Box::new(packet),
+                },
+                self,
+            );
+            self.outbound_packets.push(OutboundPacket { link, dest, packet });
+        }
+    }
+
+    fn update_classes(&mut self, full: bool) {
+        if self.classes.is_empty() {
+            return;
+        }
+        self.sync_class_links();
+        self.sync_class_seqno();
+        for router in self.classes.values_mut() {
+            if full {
+                router.full_update();
+            } else {
+                router.update();
+            }
+        }
+        self.collect_class_output();
+    }
+
+    // endregion
+
     // region Route Selection
 
+    /// Forgets the forwarded seqno requests that have not been answered, so that a request that was lost is forwarded again
+    /// when it is repeated. Answered requests are kept, so duplicates of them are still ignored.
+    fn forget_lost_seqno_requests(&mut self) {
+        let routes = &self.routes;
+        self.seqno_requests.retain(|src, seqno| {
+            routes.get(src).is_some_and(|route| !seqno_less_than(route.source.data().seqno, *seqno))
+        });
+    }
+
     pub fn solve_starvation(&mut self) {
         let mut packets = Vec::new();
         for (addr, route) in &self.routes {
-            // check if starved
-            if route.metric == INF {
+            // check if starved, an expired route is only discovered again when it is requested
+            if route.metric == INF && !self.is_expired(addr, route) {
                 // starved
                 let cur_seqno = self.get_seqno_for(addr);
                 if let Some(seqno) = cur_seqno {
@@ -134,72 +1162,192 @@ impl<T: RoutingSystem> Router<T> {
         let fd = selected_route.fd;
         let s = selected_route.source.data().seqno;
         let n = new_route.source.data().seqno;
-        if seqno_less_than(n, s) {
-            return None;
+        if seqno_less_than(n, s) || metric == INF {
+            return None; // an unreachable route is never selected, even if it has a newer seqno
         }
-        if metric < fd || seqno_less_than(s, n)
-            || (metric == fd && selected_route.metric == INF) // TODO: Prove why this is valid, and doesnt cause issues...
-        {
+        // the metric must be strictly less than the fd. Accepting metric == fd, even for a retracted route, is not loop-free:
+        // sum_inf saturates at INF - 1, so a route that loops back through us can have the same metric as our fd.
+        if metric < fd || seqno_less_than(s, n) {
             return Some(metric);
         }
         None
     }
 
-    /// Recalculate routes based on current data
-    pub fn update_routes(&mut self) {
-        // handle route retractions
-        let mut retractions = Vec::new();
-        for (_addr, route) in &mut self.routes {
-            let link = &route.link;
-            // check if link still exists
-            if !self.links.contains_key(link) || self.links.get(link).unwrap().metric == INF{
-                route.metric = INF;
-                if !route.retracted{
-                    retractions.push(route.source.clone());
-                }
-                route.retracted = true;
-            }
-        }
+    /// Forces the next call to update_routes to recompute every source.
+    /// Call this after modifying `routes` or `Neighbour::routes` directly.
+    pub fn invalidate_routes(&mut self) {
+        self.dirty_all = true;
+    }
+
+    /// Returns the sources that have to be recomputed, based on the links that changed since the last call
+    fn take_dirty_sources(&mut self) -> RootSet<T::NodeAddress> {
+        let mut dirty_links = RootSet::new();
         for (link, neigh) in &mut self.links {
             if neigh.metric == 0 {
                 self.warnings.push_back(MetricIsZero {link: link.clone()});
                 neigh.metric = 1;
             }
-            for (src, neigh_route) in &neigh.routes {
-                if *src == self.address{
-                    continue; // we can safely ignore a route to ourself
+            if self.link_state.get(link).is_none_or(|(addr, metric)| *addr != neigh.addr || *metric != neigh.metric) {
+                dirty_links.insert(link.clone());
+            }
+        }
+        for link in self.link_state.keys() {
+            if !self.links.contains_key(link) {
+                dirty_links.insert(link.clone()); // the link was removed
+            }
+        }
+        self.link_state = self.links.iter()
+            .map(|(link, neigh)| (link.clone(), (neigh.addr.clone(), neigh.metric)))
+            .collect();
+
+        let mut dirty = std::mem::take(&mut self.dirty_sources);
+        if std::mem::take(&mut self.dirty_all) {
+            dirty.extend(self.routes.keys().cloned());
+            for neigh in self.links.values() {
+                dirty.extend(neigh.routes.keys().cloned());
+            }
+        } else if !dirty_links.is_empty() {
+            // every source that is advertised over, or routed through a changed link
+            for (src, route) in &self.routes {
+                if dirty_links.contains(&route.link) {
+                    dirty.insert(src.clone());
+                }
+            }
+            for link in &dirty_links {
+                if let Some(neigh) = self.links.get(link) {
+                    dirty.extend(neigh.routes.keys().cloned());
                 }
+            }
+        }
+        dirty
+    }
 
-                let metric = sum_inf(neigh.metric, neigh_route.metric);
+    /// Recalculate routes based on current data
+    ///
+    /// Only the sources that may have changed since the last call are recomputed: sources with new updates from
+    /// neighbours, sources advertised over or routed through a link that was added, removed or had its metric changed,
+    /// and sources whose selected route changed in the last call. The result is identical to recomputing every source.
+    ///
+    /// While the selected route is reachable, a feasible route over another link replaces it only if [RoutingSystem::RouteSelector] prefers it.
+    /// With [LowestMetric], among feasible routes of equal metric, the currently selected route is kept. Otherwise, the route over the
+    /// first link in iteration order is selected, which is the smallest `Link`.
+    ///
+    /// While the selected route is reachable, a route over another link only replaces it after beating it by more than
+    /// [RoutingSystem::SWITCH_THRESHOLD] for [RoutingSystem::SWITCH_HOLD_ROUNDS] full updates.
+    pub fn update_routes(&mut self) {
+        let hysteresis = T::SWITCH_THRESHOLD > 0 || T::SWITCH_HOLD_ROUNDS > 0;
+        let mut retractions = Vec::new();
+        let mut flaps = Vec::new();
+        for src in self.take_dirty_sources() {
+            if src == self.address{
+                continue; // we can safely ignore a route to ourself
+            }
+            let previous = self.routes.get(&src).cloned();
+
+            // handle route retractions
+            if let Some(route) = self.routes.get_mut(&src) {
+                let link = &route.link;
+                // check if link still exists
+                if !self.links.contains_key(link) || self.links.get(link).unwrap().metric == INF{
+                    route.metric = INF;
+                    if !route.retracted{
+                        // neighbours already see a suppressed route as unreachable
+                        if !Self::is_suppressed_in(&self.flap_penalties, &src) {
+                            retractions.push(route.source.clone());
+                        }
+                        flaps.push(src.clone());
+                    }
+                    route.retracted = true;
+                }
+            }
+            let mut candidate_seen = false;
+            for (link, neigh) in &self.links {
+                let Some(neigh_route) = neigh.routes.get(&src) else {
+                    continue;
+                };
+
+                let hop_count = neigh_route.hop_count.saturating_add(1);
+                let metric = if hop_count > T::MAX_DIAMETER {
+                    INF // the route is too long
+                } else {
+                    sum_inf(neigh.metric, neigh_route.metric)
+                };
 
                 // if the table has the route
-                if let Some(table_route) = self.routes.get_mut(src) {
+                if let Some(table_route) = self.routes.get_mut(&src) {
                     // update route table if the entry is better
                     if let Some(new_fd) = Self::is_feasible(table_route, neigh_route, metric) {
+                        if table_route.link != *link && table_route.metric != INF {
+                            let selected = RouteCandidate {
+                                link: &table_route.link,
+                                neighbour: &self.links[&table_route.link],
+                                source: &table_route.source,
+                                metric: table_route.metric,
+                                hop_count: table_route.hop_count,
+                            };
+                            let candidate = RouteCandidate {
+                                link,
+                                neighbour: neigh,
+                                source: &neigh_route.source,
+                                metric,
+                                hop_count,
+                            };
+                            if !self.route_selector.prefer(&candidate, &selected) {
+                                continue;
+                            }
+                        }
+                        if hysteresis && table_route.link != *link && table_route.metric != INF {
+                            // the selected route still works, only switch to a route that is clearly better, and has been for a while
+                            if sum_inf(metric, T::SWITCH_THRESHOLD) >= table_route.metric {
+                                continue;
+                            }
+                            match self.switch_candidates.get(&src) {
+                                Some((candidate, since)) if candidate == link => {
+                                    candidate_seen = true;
+                                    if self.rounds - since < T::SWITCH_HOLD_ROUNDS {
+                                        continue;
+                                    }
+                                }
+                                Some(_) => continue, // another route is already waiting to replace the selected route
+                                None => {
+                                    self.switch_candidates.insert(src.clone(), (link.clone(), self.rounds));
+                                    candidate_seen = true;
+                                    if T::SWITCH_HOLD_ROUNDS > 0 {
+                                        continue;
+                                    }
+                                }
+                            }
+                            self.switch_candidates.remove(&src);
+                        }
                         // we have a better route!
                         table_route.metric = metric;
                         table_route.source = neigh_route.source.clone();
                         table_route.fd = new_fd;
                         table_route.link = link.clone();
                         table_route.next_hop = neigh.addr.clone();
+                        table_route.hop_count = hop_count;
                         table_route.retracted = false;
                     } else {
-                        let nh = &table_route.next_hop;
                         let fd = table_route.fd;
                         // this is a selected route, we should update this regardless.
-                        if *nh == neigh.addr {
+                        // compare links rather than next hops, there may be several links to the same neighbour
+                        if table_route.link == *link {
                             // update route metric
                             if metric > fd {
                                 // infeasible route, we should retract this
                                 table_route.metric = INF;
                                 if !table_route.retracted{
-                                    retractions.push(table_route.source.clone());
+                                    if !Self::is_suppressed_in(&self.flap_penalties, &src) {
+                                        retractions.push(table_route.source.clone());
+                                    }
+                                    flaps.push(src.clone());
                                 }
                                 table_route.retracted = true;
                             } else {
                                 // same or better route
                                 table_route.metric = metric;
                                 table_route.fd = metric;
+                                table_route.hop_count = hop_count;
                                 table_route.retracted = false;
                             }
                         }
@@ -212,38 +1360,131 @@ impl<T: RoutingSystem> Router<T> {
                         fd: metric,
                         link: link.clone(),
                         next_hop: neigh.addr.clone(),
+                        hop_count,
                         retracted: false,
                     };
                     self.routes.insert(src.clone(), n_route);
                 }
             }
+
+            if !candidate_seen {
+                self.switch_candidates.remove(&src); // the route waiting to be switched to is no longer better
+            }
+
+            // a changed route may be affected by neighbours that were visited before the change, check it again next time
+            let changed = match (&previous, self.routes.get(&src)) {
+                (Some(old), Some(new)) => !Self::same_route(old, new),
+                (None, None) => false,
+                _ => true
+            };
+            if changed {
+                self.dirty_sources.insert(src);
+            }
         }
 
+        for src in flaps {
+            self.add_flap_penalty(src);
+        }
         for retract in retractions {
             self.write_retraction_for(retract);
         }
     }
+
+    // region Flap damping
+
+    /// whether the destination is suppressed for flapping, it is advertised as unreachable until its penalty decays
+    pub fn is_suppressed(&self, addr: &T::NodeAddress) -> bool {
+        Self::is_suppressed_in(&self.flap_penalties, addr)
+    }
+
+    fn is_suppressed_in(penalties: &RootMap<T::NodeAddress, (f64, bool)>, addr: &T::NodeAddress) -> bool {
+        penalties.get(addr).is_some_and(|(_, suppressed)| *suppressed)
+    }
+
+    /// the metric we advertise for a route, suppressed routes are advertised as unreachable
+    fn advertised_metric(&self, route: &Route<T>) -> u16 {
+        if self.is_suppressed(&route.source.data().addr) {
+            INF
+        } else {
+            route.metric
+        }
+    }
+
+    /// penalizes the destination for being retracted, and suppresses it if it flaps too often
+    fn add_flap_penalty(&mut self, addr: T::NodeAddress) {
+        if T::FLAP_PENALTY == 0 {
+            return;
+        }
+        let (penalty, suppressed) = self.flap_penalties.entry(addr.clone()).or_insert((0.0, false));
+        *penalty += T::FLAP_PENALTY as f64;
+        if !*suppressed && *penalty > T::FLAP_SUPPRESS_THRESHOLD as f64 {
+            *suppressed = true;
+            self.warn(RouteSuppressed { addr });
+        }
+    }
+
+    /// decays the penalties by one round, suppressed destinations are advertised again in this round's broadcast
+    fn decay_flap_penalties(&mut self) {
+        let decay = 0.5f64.powf(1.0 / T::FLAP_HALF_LIFE_ROUNDS.max(1) as f64);
+        self.flap_penalties.retain(|_, (penalty, suppressed)| {
+            *penalty *= decay;
+            if *penalty < T::FLAP_REUSE_THRESHOLD as f64 {
+                *suppressed = false;
+            }
+            *suppressed || *penalty >= 1.0
+        });
+    }
+
+    // endregion
+
+    fn same_route(a: &Route<T>, b: &Route<T>) -> bool {
+        a.metric == b.metric && a.fd == b.fd && a.link == b.link && a.next_hop == b.next_hop && a.hop_count == b.hop_count
+            && a.retracted == b.retracted && a.source.data().seqno == b.source.data().seqno
+    }
     // endregion
 
     // pushes updates to neighbours
     pub fn broadcast_routes(&mut self) {
         let mut vec = Vec::new();
-        for route in self.routes.values() {
+        for (src, route) in &self.routes {
+            if T::ON_DEMAND && !self.proactive.contains(src) {
+                continue; // on-demand routes are only sent in answer to a discovery
+            }
             vec.push(RouteUpdate {
                 source: route.source.clone(),
-                metric: route.metric,
+                metric: self.advertised_metric(route),
+                hop_count: route.hop_count,
             })
         }
-        vec.push(RouteUpdate{
-            source: self.mac_sys.sign(
-                Source {
-                    addr: self.address.clone(),
-                    seqno: self.seqno,
-                },
-                self,
-            ),
-            metric: 0
-        });
+        for (addr, seqno) in &self.anycast {
+            if !T::ON_DEMAND || self.proactive.contains(addr) {
+                vec.push(self.origin_update(addr, *seqno, 0));
+            }
+        }
+        for (addr, route) in &self.static_routes {
+            if route.advertised && (!T::ON_DEMAND || self.proactive.contains(addr)) {
+                vec.push(self.injected_update(addr, route));
+            }
+        }
+        // on-demand destinations only advertise themselves in answer to a discovery
+        if !T::ON_DEMAND || self.proactive.contains(&self.address) {
+            vec.push(RouteUpdate{
+                source: self.mac_sys.sign(
+                    Source {
+                        addr: self.address.clone(),
+                        seqno: self.seqno,
+                        injected: false,
+                    },
+                    self,
+                ),
+                metric: 0,
+                hop_count: 0
+            });
+        }
+        if self.is_border() {
+            self.write_border_routes(vec);
+            return;
+        }
         self.write_broadcast_packet(&self.mac_sys.sign(
             Packet::BatchRouteUpdate { routes: vec },
             self,
@@ -251,7 +1492,7 @@ impl<T: RoutingSystem> Router<T> {
     }
     /// you should call this after calling update routes, otherwise the seqno metrics published is not the best...
     pub fn broadcast_seqno_updates(&mut self) {
-        let tmp_seqno = self.broadcast_route_for.drain().collect::<Vec<T::NodeAddress>>();
+        let tmp_seqno = std::mem::take(&mut self.broadcast_route_for);
         for source in tmp_seqno {
             if let Some(pkt) = self.create_seqno_packet(&source) {
                 self.write_broadcast_packet(&pkt);
@@ -261,6 +1502,15 @@ impl<T: RoutingSystem> Router<T> {
 
     /// Creates a seqno packet using the data we already have
     fn create_seqno_packet(&self, addr: &T::NodeAddress) -> Option<MAC<Packet<T>, T>> {
+        if let Some(seqno) = self.anycast.get(addr) {
+            return Some(self.mac_sys.sign(Packet::UrgentRouteUpdate(self.origin_update(addr, *seqno, 0)), self));
+        }
+        if let Some(route) = self.static_routes.get(addr).filter(|route| route.advertised) {
+            return Some(self.mac_sys.sign(Packet::UrgentRouteUpdate(self.injected_update(addr, route)), self));
+        }
+        if self.area().as_ref() == Some(addr) {
+            return self.is_border().then(|| self.mac_sys.sign(Packet::UrgentRouteUpdate(self.summary_update()), self));
+        }
         if *addr == self.address{
             return Some(self.mac_sys.sign(
                 Packet::UrgentRouteUpdate(RouteUpdate{
@@ -268,10 +1518,12 @@ impl<T: RoutingSystem> Router<T> {
                         Source {
                             addr: self.address.clone(),
                             seqno: self.seqno,
+                            injected: false,
                         },
                         self,
                     ),
-                    metric: 0
+                    metric: 0,
+                    hop_count: 0
                 }),
                 self,
             ));
@@ -280,7 +1532,8 @@ impl<T: RoutingSystem> Router<T> {
             return Some(self.mac_sys.sign(
                 Packet::UrgentRouteUpdate(RouteUpdate {
                     source: route.source.clone(),
-                    metric: route.metric,
+                    metric: self.advertised_metric(route),
+                    hop_count: route.hop_count,
                 }),
                 self,
             ));
@@ -294,6 +1547,7 @@ impl<T: RoutingSystem> Router<T> {
             Packet::UrgentRouteUpdate(RouteUpdate {
                 source,
                 metric: INF,
+                hop_count: 0,
             }),
             self,
         ))
@@ -318,22 +1572,16 @@ impl<T: RoutingSystem> Router<T> {
         match data.data() {
             Packet::UrgentRouteUpdate(route) => {
                 // println!("[dbg] {} got packet {} from {}", json!(self.address), json!(data), json!(neigh));
-                match self.handle_neighbour_route_update(route, link, neigh)? {
-                    SeqnoUpdate => {
-                        // let's rebroadcast this change, our seqno has increased!
-                        self.broadcast_route_for
-                            .insert(route.source.data().addr.clone());
-                    }
-                    Retraction => {
-                        // broadcast this retraction
-                        self.write_retraction_for(route.source.clone());
-                    }
-                    NoAction => {}
+                self.handle_urgent_route_update(route, link)?;
+            }
+            Packet::MergedRouteUpdate { routes } => {
+                for route in routes {
+                    self.handle_urgent_route_update(route, link)?;
                 }
             }
             Packet::BatchRouteUpdate { routes } => {
                 for route in routes {
-                    self.handle_neighbour_route_update(route, link, neigh)?; // we dont need to worry about seqno updates and retractions
+                    self.handle_neighbour_route_update(route, link)?; // we dont need to worry about seqno updates and retractions
                 }
             }
             Packet::SeqnoRequest { source, seqno } => {
@@ -361,6 +1609,27 @@ impl<T: RoutingSystem> Router<T> {
                             });
                         }
                         self.broadcast_route_for.insert(self.address.clone());
+                    } else if let Some(route) = self.static_routes.get_mut(source).filter(|route| route.advertised) {
+                        // we inject the route, like an anycast address
+                        increment(&mut route.seqno);
+                        if seqno_less_than(route.seqno, *seqno) {
+                            route.seqno = *seqno;
+                        }
+                        self.broadcast_route_for.insert(source.clone());
+                    } else if let Some(anycast_seqno) = self.anycast.get_mut(source) {
+                        // we are one of the origins of the address, the others may have answered with a higher seqno
+                        increment(anycast_seqno);
+                        if seqno_less_than(*anycast_seqno, *seqno) {
+                            *anycast_seqno = *seqno;
+                        }
+                        self.broadcast_route_for.insert(source.clone());
+                    } else if self.area().as_ref() == Some(source) {
+                        // we originate the summary of our area, the other border routers may have answered with a higher seqno
+                        increment(&mut self.area_seqno);
+                        if seqno_less_than(self.area_seqno, *seqno) {
+                            self.area_seqno = *seqno;
+                        }
+                        self.broadcast_route_for.insert(source.clone());
                     } else {
                         let req_seqno = self.seqno_requests.entry(source.clone()).or_insert(0);
                         // prevent duplication and infinite amplification... :skull:
@@ -379,18 +1648,149 @@ impl<T: RoutingSystem> Router<T> {
                             // println!("[dbg] ignoring request, de-duplication");
                         }
                     }
+                } else if T::ON_DEMAND && !self.discoveries.contains_key(source) {
+                    // a route discovery, forward it, and the answer when it arrives
+                    self.discoveries.insert(source.clone(), self.rounds);
+                    self.write_broadcast_packet(&self.mac_sys.sign(
+                        Packet::SeqnoRequest {
+                            source: source.clone(),
+                            seqno: *seqno,
+                        },
+                        self,
+                    ));
                 } else {
                     // println!("[dbg] ignoring request, we dont have seqno for requested {}", json!(source));
                 }
             }
+            Packet::AckRequest { nonce, packet } => {
+                self.handle_packet(packet, link, neigh)?;
+                let ack = self.mac_sys.sign(Packet::Ack { nonce: *nonce }, self);
+                self.outbound_packets.push(OutboundPacket {
+                    link: link.clone(),
+                    dest: neigh.clone(),
+                    packet: ack,
+                });
+            }
+            Packet::Hello { version, capabilities, answer } => {
+                if let Some(neighbour) = self.links.get_mut(link) {
+                    neighbour.protocol = NeighbourProtocol::Known {
+                        version: *version,
+                        capabilities: *capabilities,
+                    };
+                    // the neighbour may not know our protocol, it may have restarted or lost our hello
+                    if !answer {
+                        self.write_hello(link.clone(), neigh.clone(), true);
+                    }
+                }
+            }
+            Packet::Ack { nonce } => {
+                if let Some(pending) = self.unacknowledged.get_mut(link) {
+                    pending.updates.retain(|_, (sent, _)| sent != nonce);
+                    pending.retransmissions = 0;
+                }
+            }
+            Packet::ClassPacket { class, packet } => {
+                // a neighbour may route classes that we do not
+                if *class != 0 && self.classes.contains_key(class) {
+                    self.sync_class_links();
+                    let result = self.classes.get_mut(class).unwrap().handle_packet(packet, link, neigh);
+                    self.sync_class_seqno();
+                    self.collect_class_output();
+                    result?;
+                }
+            }
+            Packet::AttributeUpdate { attributes } => {
+                self.handle_attribute_update(attributes, link)?;
+            }
+            Packet::LinkStateUpdate { states } => {
+                self.handle_link_state_update(states, link)?;
+            }
+            Packet::Extension { data } => {
+                if self.extensions.len() >= T::MAX_EXTENSION_LENGTH {
+                    self.extensions.pop_front();
+                }
+                self.extensions.push_back(ReceivedExtension {
+                    link: link.clone(),
+                    neighbour: neigh.clone(),
+                    data: data.clone(),
+                });
+            }
         }
         Ok(())
     }
 
+    fn handle_urgent_route_update(&mut self, route: &RouteUpdate<T>, link: &T::Link) -> Result<(), RoutingError<T>> {
+        match self.handle_neighbour_route_update(route, link)? {
+            SeqnoUpdate => {
+                // let's rebroadcast this change, our seqno has increased!
+                self.broadcast_route_for
+                    .insert(route.source.data().addr.clone());
+            }
+            Retraction => {
+                // broadcast this retraction, unless the route is already advertised as unreachable
+                if !self.is_suppressed(&route.source.data().addr) {
+                    self.write_retraction_for(route.source.clone());
+                }
+            }
+            NoAction => {}
+        }
+        Ok(())
+    }
+
+    /// Audits the internal consistency of the router, returns every violation that was found.
+    /// Call this after an update, as links that were just removed or changed are only reflected in the routes by update_routes.
+    pub fn check_invariants(&self) -> Vec<ConsistencyViolation<T>> {
+        let mut violations = Vec::new();
+        for (src, route) in &self.routes {
+            if *src == self.address {
+                violations.push(ConsistencyViolation::RouteToSelf);
+                continue;
+            }
+            // a retracted route may still refer to the link it was retracted from
+            if route.retracted {
+                continue;
+            }
+            match self.links.get(&route.link) {
+                None => violations.push(ConsistencyViolation::MissingLink {
+                    source: src.clone(),
+                    link: route.link.clone(),
+                }),
+                Some(neigh) if neigh.addr != route.next_hop => violations.push(ConsistencyViolation::WrongNextHop {
+                    source: src.clone(),
+                    next_hop: route.next_hop.clone(),
+                    neighbour: neigh.addr.clone(),
+                }),
+                _ => {}
+            }
+            if route.fd > route.metric {
+                violations.push(ConsistencyViolation::FeasibilityDistanceAboveMetric {
+                    source: src.clone(),
+                    fd: route.fd,
+                    metric: route.metric,
+                });
+            }
+        }
+        for source in self.seqno_requests.keys() {
+            if !self.routes.contains_key(source) {
+                violations.push(ConsistencyViolation::UnknownSeqnoRequest { source: source.clone() });
+            }
+        }
+        violations
+    }
+
     pub fn get_seqno_for(&self, addr: &T::NodeAddress) -> Option<u16> {
         if *addr == self.address{
             return Some(self.seqno);
         }
+        if let Some(seqno) = self.anycast.get(addr) {
+            return Some(*seqno);
+        }
+        if let Some(route) = self.static_routes.get(addr).filter(|route| route.advertised) {
+            return Some(route.seqno);
+        }
+        if self.area().as_ref() == Some(addr) {
+            return self.is_border().then_some(self.area_seqno);
+        }
         if let Some(x) = self.routes.get(addr) {
             let data = x.source.data();
             return Some(data.seqno);
@@ -402,15 +1802,29 @@ impl<T: RoutingSystem> Router<T> {
     fn handle_neighbour_route_update(
         &mut self,
         update: &RouteUpdate<T>,
-        link: &T::Link,
-        neigh: &T::NodeAddress
+        link: &T::Link
     ) -> Result<UpdateAction, RoutingError<T>> {
-        let Source { addr: src, seqno } = update.source.data();
+        let Source { addr: src, seqno, injected } = update.source.data();
 
         if *src == self.address{
-            // just ignore this
+            // a route to us with a newer seqno, we lost our seqno, or a static route to our address was injected elsewhere
+            if T::TRUST_RESYNC_SEQNO && seqno_less_than(self.seqno, *seqno) && self.mac_sys.validate(&update.source, src) {
+                let original = self.seqno;
+                self.seqno = increment_by(*seqno, 1);
+                // taking our address back from a static route is expected, and not a desynchronization
+                if !*injected {
+                    self.warn(DesynchronizedSeqno {
+                        old_seqno: original,
+                        new_seqno: self.seqno
+                    });
+                }
+                self.broadcast_route_for.insert(self.address.clone());
+            }
             return Ok(NoAction);
         }
+        if !self.accepts_source(src) {
+            return Ok(NoAction); // the route belongs inside another area, or summarizes ours
+        }
 
         // validate update
         if !self.mac_sys.validate(&update.source, src) {
@@ -419,6 +1833,37 @@ impl<T: RoutingSystem> Router<T> {
             });
         }
 
+        if let Some(anycast_seqno) = self.anycast.get_mut(src) {
+            // another origin of the address, follow its seqno so that our own routes are not ignored as outdated
+            if seqno_less_than(*anycast_seqno, *seqno) {
+                *anycast_seqno = *seqno;
+            }
+            return Ok(NoAction);
+        }
+        if let Some(route) = self.static_routes.get_mut(src).filter(|route| route.advertised) {
+            if route.distance < T::LEARNED_DISTANCE {
+                if *injected || update.metric == INF || seqno_less_than(*seqno, route.seqno) {
+                    // another router that injects the address, a route through us, or a route to the node from before we injected it
+                    if seqno_less_than(route.seqno, *seqno) {
+                        route.seqno = *seqno;
+                    }
+                    return Ok(NoAction);
+                }
+                // the node that owns the address is back, keep our preference to ourselves and learn its route
+                route.advertised = false;
+                let retraction = self.injected_update(src, &self.static_routes[src]).source;
+                self.write_retraction_for(retraction);
+            } else {
+                // a route through us has our seqno and a higher metric, so it is safe to use a learned route that has neither
+                let other_origin = seqno_less_than(route.seqno, *seqno) || (route.seqno == *seqno && update.metric <= route.metric);
+                if !other_origin {
+                    return Ok(NoAction);
+                }
+                route.active = false;
+                route.advertised = false;
+            }
+        }
+
         let mut action = NoAction;
         let stored_seqno = self.get_seqno_for(src);
         if let Some(d_seqno) = stored_seqno {
@@ -432,10 +1877,11 @@ impl<T: RoutingSystem> Router<T> {
         // check if this route is the currently selected route
         let mut selected = false;
         if let Some(route) = self.routes.get(src){
-            selected = route.next_hop == *neigh;
+            selected = route.link == *link;
         }
 
         if let Some(neighbour) = self.links.get_mut(link) {
+            self.dirty_sources.insert(src.clone());
             // update the value
             if let Some(table_route) = neighbour.routes.get_mut(src){
                 table_route.source = update.source.clone();
@@ -454,6 +1900,8 @@ impl<T: RoutingSystem> Router<T> {
                 }
                 else{
                     table_route.metric = update.metric;
+                    table_route.hop_count = update.hop_count;
+                    table_route.retracted = false; // the route is advertised again, so it can be retracted again
                 }
             }
             else if update.metric != INF || selected {
@@ -461,6 +1909,7 @@ impl<T: RoutingSystem> Router<T> {
                 let route = ExternalRoute {
                 source: update.source.clone(),
                     metric: update.metric,
+                    hop_count: update.hop_count,
                     retracted: update.metric == INF
                 };
                 neighbour.routes.insert(src.clone(), route);
@@ -470,6 +1919,133 @@ impl<T: RoutingSystem> Router<T> {
     }
 }
 
+/// The entries of a map, serialized as a list since the keys may not be strings
+#[cfg(any(feature = "sim", feature = "record"))]
+type FrozenMap<'a, K, V> = Vec<(&'a K, &'a V)>;
+#[cfg(any(feature = "sim", feature = "record"))]
+type ThawedMap<K, V> = Vec<(K, V)>;
+
+/// A router together with the state that is skipped when the router itself is serialized:
+/// its links, routes and classes, and the protocol state that is kept between full updates
+#[cfg(any(feature = "sim", feature = "record"))]
+#[derive(Serialize)]
+#[serde(bound = "")]
+pub(crate) struct FrozenRouter<'a, T: RoutingSystem> {
+    router: &'a Router<T>,
+    links: FrozenMap<'a, T::Link, Neighbour<T>>,
+    routes: FrozenMap<'a, T::NodeAddress, Route<T>>,
+    classes: Vec<(&'a u8, FrozenRouter<'a, T>)>,
+    node_attributes: FrozenMap<'a, T::NodeAddress, MAC<NodeAttributes<T>, T>>,
+    topology: FrozenMap<'a, T::NodeAddress, MAC<LinkState<T>, T>>,
+    rounds: u64,
+    switch_candidates: FrozenMap<'a, T::NodeAddress, (T::Link, u64)>,
+    flap_penalties: FrozenMap<'a, T::NodeAddress, (f64, bool)>,
+    next_nonce: u32,
+    unacknowledged: FrozenMap<'a, T::Link, PendingAcks<T>>,
+    broadcast_attributes_for: Vec<&'a T::NodeAddress>,
+    advertised_links: FrozenMap<'a, T::Link, (T::NodeAddress, u16)>,
+    broadcast_link_state_for: Vec<&'a T::NodeAddress>,
+    discoveries: FrozenMap<'a, T::NodeAddress, u64>,
+    route_usage: FrozenMap<'a, T::NodeAddress, u64>,
+}
+
+#[cfg(any(feature = "sim", feature = "record"))]
+impl<'a, T: RoutingSystem> FrozenRouter<'a, T> {
+    pub(crate) fn new(router: &'a Router<T>) -> Self {
+        Self {
+            router,
+            links: router.links.iter().collect(),
+            routes: router.routes.iter().collect(),
+            classes: router.classes.iter().map(|(class, router)| (class, FrozenRouter::new(router))).collect(),
+            node_attributes: router.node_attributes.iter().collect(),
+            topology: router.topology.iter().collect(),
+            rounds: router.rounds,
+            switch_candidates: router.switch_candidates.iter().collect(),
+            flap_penalties: router.flap_penalties.iter().collect(),
+            next_nonce: router.next_nonce,
+            unacknowledged: router.unacknowledged.iter().collect(),
+            broadcast_attributes_for: router.broadcast_attributes_for.iter().collect(),
+            advertised_links: router.advertised_links.iter().collect(),
+            broadcast_link_state_for: router.broadcast_link_state_for.iter().collect(),
+            discoveries: router.discoveries.iter().collect(),
+            route_usage: router.route_usage.iter().collect(),
+        }
+    }
+}
+
+/// A [FrozenRouter] that is read back, the protocol state defaults to empty for routers frozen by older releases
+#[cfg(any(feature = "sim", feature = "record"))]
+#[derive(Deserialize)]
+#[serde(bound = "")]
+pub(crate) struct ThawedRouter<T: RoutingSystem> {
+    router: Router<T>,
+    links: ThawedMap<T::Link, Neighbour<T>>,
+    routes: ThawedMap<T::NodeAddress, Route<T>>,
+    #[serde(default)]
+    classes: Vec<(u8, ThawedRouter<T>)>,
+    #[serde(default)]
+    node_attributes: ThawedMap<T::NodeAddress, MAC<NodeAttributes<T>, T>>,
+    #[serde(default)]
+    topology: ThawedMap<T::NodeAddress, MAC<LinkState<T>, T>>,
+    #[serde(default)]
+    rounds: u64,
+    #[serde(default)]
+    switch_candidates: ThawedMap<T::NodeAddress, (T::Link, u64)>,
+    #[serde(default)]
+    flap_penalties: ThawedMap<T::NodeAddress, (f64, bool)>,
+    #[serde(default)]
+    next_nonce: u32,
+    #[serde(default)]
+    unacknowledged: ThawedMap<T::Link, PendingAcks<T>>,
+    #[serde(default)]
+    broadcast_attributes_for: Vec<T::NodeAddress>,
+    #[serde(default)]
+    advertised_links: ThawedMap<T::Link, (T::NodeAddress, u16)>,
+    #[serde(default)]
+    broadcast_link_state_for: Vec<T::NodeAddress>,
+    #[serde(default)]
+    discoveries: ThawedMap<T::NodeAddress, u64>,
+    #[serde(default)]
+    route_usage: ThawedMap<T::NodeAddress, u64>,
+}
+
+#[cfg(any(feature = "sim", feature = "record"))]
+impl<T: RoutingSystem> ThawedRouter<T> {
+    pub(crate) fn thaw(self) -> Router<T> {
+        let mut router = self.router;
+        router.links = self.links.into_iter().collect();
+        router.routes = self.routes.into_iter().collect();
+        router.classes = self.classes.into_iter().map(|(class, router)| (class, router.thaw())).collect();
+        router.node_attributes = self.node_attributes.into_iter().collect();
+        router.topology = self.topology.into_iter().collect();
+        router.rounds = self.rounds;
+        router.switch_candidates = self.switch_candidates.into_iter().collect();
+        router.flap_penalties = self.flap_penalties.into_iter().collect();
+        router.next_nonce = self.next_nonce;
+        router.unacknowledged = self.unacknowledged.into_iter().collect();
+        router.broadcast_attributes_for = self.broadcast_attributes_for.into_iter().collect();
+        router.advertised_links = self.advertised_links.into_iter().collect();
+        router.broadcast_link_state_for = self.broadcast_link_state_for.into_iter().collect();
+        router.discoveries = self.discoveries.into_iter().collect();
+        router.route_usage = self.route_usage.into_iter().collect();
+        router.invalidate_routes();
+        router
+    }
+}
+
+/// Selects the route with the newest seqno, then the lowest metric
+#[derive(Default)]
+pub struct LowestMetric {}
+
+impl<T: RoutingSystem + ?Sized> RouteSelector<T> for LowestMetric {
+    fn prefer(&self, candidate: &RouteCandidate<T>, selected: &RouteCandidate<T>) -> bool {
+        let candidate_seqno = candidate.source.data().seqno;
+        let selected_seqno = selected.source.data().seqno;
+        seqno_less_than(selected_seqno, candidate_seqno)
+            || (candidate_seqno == selected_seqno && candidate.metric < selected.metric)
+    }
+}
+
 #[derive(Default)]
 pub struct NoMACSystem {
 
@@ -502,13 +2078,13 @@ impl<V: RootData, T: RoutingSystem + ?Sized> MACSignature<V, T> for DummyMAC<V>{
 
 impl<T: RoutingSystem + ?Sized> MACSystem<T> for NoMACSystem {
     type MACSignatureType<V: RootData> = DummyMAC<V>;
-    fn sign<V: RootData>(&self, data: V, router: &Router<T>) -> DummyMAC<V>{
+    fn sign<V: RootData>(&self, data: V, _router: &Router<T>) -> DummyMAC<V>{
         DummyMAC{
             data
         }
     }
 
-    fn validate<V: RootData>(&self, sig: &MAC<V, T>, subject: &T::NodeAddress) -> bool {
+    fn validate<V: RootData>(&self, _sig: &MAC<V, T>, _subject: &T::NodeAddress) -> bool {
         true
     }
 }
\ No newline at end of file
@@ -0,0 +1,270 @@
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+use crate::concepts::neighbour::Neighbour;
+use crate::concepts::packet::Packet;
+use crate::concepts::route::Route;
+use crate::feedback::{ConsistencyViolation, RoutingError};
+use crate::framework::{MAC, RoutingSystem};
+use crate::router::Router;
+use crate::util::RootMap;
+
+/// A bidirectional link with a real latency and bandwidth, both routers refer to it with the same `Link`
+pub struct EventLink<T: RoutingSystem + ?Sized> {
+    pub link: T::Link,
+    pub a: T::NodeAddress,
+    pub b: T::NodeAddress,
+    /// metric of the link in both directions, INF if the link is down
+    pub metric: u16,
+    /// one-way propagation delay, in milliseconds
+    pub latency: u64,
+    /// bytes per second in each direction, 0 for unlimited
+    pub bandwidth: u64,
+    /// time at which each direction (a to b, b to a) has finished sending its queued packets
+    busy_until: [u64; 2],
+}
+
+enum Event<T: RoutingSystem + ?Sized> {
+    /// a packet arrives at its destination
+    Deliver {
+        dest: T::NodeAddress,
+        link: T::Link,
+        packet: MAC<Packet<T>, T>,
+    },
+    /// the periodic update timer of a router fires
+    Timer {
+        node: T::NodeAddress,
+    },
+    /// the metric of a link changes on both of its ends
+    LinkMetric {
+        link: T::Link,
+        metric: u16,
+    },
+}
+
+struct Scheduled<T: RoutingSystem + ?Sized> {
+    at: u64,
+    /// events scheduled for the same time are processed in the order they were scheduled
+    seq: u64,
+    event: Event<T>,
+}
+
+impl<T: RoutingSystem + ?Sized> PartialEq for Scheduled<T> {
+    fn eq(&self, other: &Self) -> bool {
+        (self.at, self.seq) == (other.at, other.seq)
+    }
+}
+
+impl<T: RoutingSystem + ?Sized> Eq for Scheduled<T> {}
+
+impl<T: RoutingSystem + ?Sized> PartialOrd for Scheduled<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: RoutingSystem + ?Sized> Ord for Scheduled<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.at, self.seq).cmp(&(other.at, other.seq))
+    }
+}
+
+/// metric and next hop of every route in a routing table
+type RouteSummary<T> = RootMap<<T as RoutingSystem>::NodeAddress, (u16, <T as RoutingSystem>::NodeAddress)>;
+
+/// A discrete-event network simulator, where time is measured in simulated milliseconds.
+///
+/// Unlike [Simulation](super::Simulation), packets take as long as their link's latency and bandwidth dictate.
+/// Routers run an update as soon as they receive a packet, and a full update whenever their periodic timer fires.
+pub struct EventSimulation<T: RoutingSystem> {
+    pub routers: Vec<Router<T>>,
+    pub links: Vec<EventLink<T>>,
+    /// the current time, in milliseconds
+    pub time: u64,
+    /// milliseconds between the periodic full updates of each router
+    pub update_interval: u64,
+    /// errors returned by the routers while handling packets, drain this regularly
+    pub errors: Vec<(T::NodeAddress, RoutingError<T>)>,
+    /// inconsistencies found in the routers after each of their updates, see [Router::check_invariants]
+    pub violations: Vec<(T::NodeAddress, ConsistencyViolation<T>)>,
+    events: BinaryHeap<Reverse<Scheduled<T>>>,
+    next_seq: u64,
+    /// metric and next hop of every route, as of the last event that touched the router
+    tables: Vec<RouteSummary<T>>,
+    /// last time any routing table changed
+    last_change: u64,
+}
+
+impl<T: RoutingSystem> EventSimulation<T> {
+    pub fn new(update_interval: u64) -> Self {
+        Self {
+            routers: Vec::new(),
+            links: Vec::new(),
+            time: 0,
+            update_interval,
+            errors: Vec::new(),
+            violations: Vec::new(),
+            events: BinaryHeap::new(),
+            next_seq: 0,
+            tables: Vec::new(),
+            last_change: 0,
+        }
+    }
+
+    fn schedule(&mut self, at: u64, event: Event<T>) {
+        self.events.push(Reverse(Scheduled { at, seq: self.next_seq, event }));
+        self.next_seq += 1;
+    }
+
+    // region Topology
+
+    /// adds a router with no links, its first periodic update happens after `phase` milliseconds
+    pub fn add_router(&mut self, addr: T::NodeAddress, phase: u64) -> &mut Self {
+        self.schedule(self.time + phase, Event::Timer { node: addr.clone() });
+        self.routers.push(Router::new(addr));
+        self.tables.push(RootMap::new());
+        self
+    }
+
+    /// connects a and b with a bidirectional link, latency is in milliseconds and bandwidth in bytes per second (0 for unlimited)
+    pub fn add_link(&mut self, link: T::Link, a: T::NodeAddress, b: T::NodeAddress, metric: u16, latency: u64, bandwidth: u64) -> &mut Self {
+        for (node, neigh) in [(&a, &b), (&b, &a)] {
+            if let Some(router) = self.routers.iter_mut().find(|r| r.address == *node) {
+                let mut neighbour = Neighbour::new(neigh.clone());
+                neighbour.metric = metric;
+                router.links.insert(link.clone(), neighbour);
+            }
+        }
+        self.links.push(EventLink { link, a, b, metric, latency, bandwidth, busy_until: [0, 0] });
+        self
+    }
+
+    /// changes the metric of a link on both of its ends, `delay` milliseconds from now
+    pub fn set_link_metric(&mut self, link: T::Link, metric: u16, delay: u64) {
+        self.schedule(self.time + delay, Event::LinkMetric { link, metric });
+    }
+
+    // endregion
+
+    // region Inspection
+
+    pub fn router(&self, addr: &T::NodeAddress) -> Option<&Router<T>> {
+        self.routers.iter().find(|r| r.address == *addr)
+    }
+
+    /// the route to dest in the table of node
+    pub fn route(&self, node: &T::NodeAddress, dest: &T::NodeAddress) -> Option<&Route<T>> {
+        self.router(node)?.routes.get(dest)
+    }
+
+    /// time of the next event, if any
+    pub fn next_event_at(&self) -> Option<u64> {
+        self.events.peek().map(|Reverse(x)| x.at)
+    }
+
+    // endregion
+
+    // region Stepping
+
+    /// puts the outbound packets of a router on its links
+    fn send_packets(&mut self, idx: usize) {
+        let sender = self.routers[idx].address.clone();
+        for packet in self.routers[idx].take_outbound_packets() {
+            let Some(link) = self.links.iter_mut().find(|x| x.link == packet.link) else {
+                continue; // the link was removed
+            };
+            let direction = if link.a == sender { 0 } else { 1 };
+            let transmission = if link.bandwidth == 0 {
+                0
+            } else {
+                let size = serde_json::to_vec(&packet.packet).map_or(0, |x| x.len()) as u64;
+                (size * 1000).div_ceil(link.bandwidth)
+            };
+            let sent = self.time.max(link.busy_until[direction]) + transmission;
+            link.busy_until[direction] = sent;
+            let arrival = sent + link.latency;
+            self.schedule(arrival, Event::Deliver { dest: packet.dest, link: packet.link, packet: packet.packet });
+        }
+    }
+
+    /// records the time if the routing table of a router has changed, and checks the router for inconsistencies
+    fn check_table(&mut self, idx: usize) {
+        for violation in self.routers[idx].check_invariants() {
+            self.violations.push((self.routers[idx].address.clone(), violation));
+        }
+        let table: RouteSummary<T> = self.routers[idx].routes.iter()
+            .map(|(addr, route)| (addr.clone(), (route.metric, route.next_hop.clone())))
+            .collect();
+        if table != self.tables[idx] {
+            self.tables[idx] = table;
+            self.last_change = self.time;
+        }
+    }
+
+    /// processes the next event, returns false if there are no events left
+    pub fn step(&mut self) -> bool {
+        let Some(Reverse(Scheduled { at, event, .. })) = self.events.pop() else {
+            return false;
+        };
+        self.time = at;
+        match event {
+            Event::Deliver { dest, link, packet } => {
+                if let Some(idx) = self.routers.iter().position(|r| r.address == dest) {
+                    let router = &mut self.routers[idx];
+                    if let Some(neigh_addr) = router.links.get(&link).map(|x| x.addr.clone()) {
+                        if let Err(err) = router.handle_packet(&packet, &link, &neigh_addr) {
+                            self.errors.push((dest, err));
+                        }
+                        router.update();
+                        self.send_packets(idx);
+                        self.check_table(idx);
+                    }
+                }
+            }
+            Event::Timer { node } => {
+                if let Some(idx) = self.routers.iter().position(|r| r.address == node) {
+                    self.routers[idx].full_update();
+                    self.send_packets(idx);
+                    self.check_table(idx);
+                    self.schedule(self.time + self.update_interval, Event::Timer { node });
+                }
+            }
+            Event::LinkMetric { link, metric } => {
+                for sim_link in self.links.iter_mut().filter(|x| x.link == link) {
+                    sim_link.metric = metric;
+                }
+                for router in &mut self.routers {
+                    router.set_link_metric(&link, metric);
+                }
+            }
+        }
+        true
+    }
+
+    /// processes every event up to and including the given time
+    pub fn run_until(&mut self, time: u64) {
+        while self.next_event_at().is_some_and(|at| at <= time) {
+            self.step();
+        }
+        self.time = self.time.max(time);
+    }
+
+    /// runs until no routing table has changed for `quiet` milliseconds, and returns how long the network took to converge.
+    /// `quiet` should span a few update intervals, so that starved routes have time to recover.
+    /// Returns None if the network has not converged after `limit` milliseconds.
+    pub fn run_until_converged(&mut self, quiet: u64, limit: u64) -> Option<u64> {
+        let start = self.time;
+        self.last_change = start;
+        while let Some(at) = self.next_event_at() {
+            if at > self.last_change + quiet {
+                break;
+            }
+            if at > start + limit {
+                return None;
+            }
+            self.step();
+        }
+        Some(self.last_change - start)
+    }
+
+    // endregion
+}
@@ -0,0 +1,148 @@
+use educe::Educe;
+use crate::concepts::route::Route;
+use crate::framework::{MACSignature, RoutingSystem};
+use crate::router::{Router, INF};
+use crate::util::{seqno_less_than, RootMap, RootSet};
+
+/// A network-wide invariant of the routing protocol that does not hold
+#[derive(Educe)]
+#[educe(Debug, Clone(bound()))]
+pub enum Violation<T: RoutingSystem + ?Sized> {
+    /// Following the next hops towards dest leads back to a router on the path.
+    /// The path is the loop itself, starting and ending with the same router.
+    ForwardingLoop {
+        dest: T::NodeAddress,
+        path: Vec<T::NodeAddress>,
+    },
+    /// The feasibility distance of a route increased, while its seqno stayed the same
+    FeasibilityDistanceIncreased {
+        node: T::NodeAddress,
+        dest: T::NodeAddress,
+        seqno: u16,
+        old_fd: u16,
+        new_fd: u16,
+    },
+    /// The seqno of a route went backwards
+    SeqnoDecreased {
+        node: T::NodeAddress,
+        dest: T::NodeAddress,
+        old_seqno: u16,
+        new_seqno: u16,
+    },
+    /// A route that is in use goes over a link that does not exist, or is down
+    MissingLink {
+        node: T::NodeAddress,
+        dest: T::NodeAddress,
+        link: T::Link,
+    },
+}
+
+/// Checks the routing tables of every router in a network after each step.
+///
+/// The checker remembers the (seqno, fd) of every route between calls to [InvariantChecker::check],
+/// so it should be given the same network every time.
+pub struct InvariantChecker<T: RoutingSystem + ?Sized> {
+    /// (node, dest) -> (seqno, fd)
+    distances: RootMap<(T::NodeAddress, T::NodeAddress), (u16, u16)>,
+}
+
+impl<T: RoutingSystem> Default for InvariantChecker<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: RoutingSystem> InvariantChecker<T> {
+    pub fn new() -> Self {
+        Self {
+            distances: RootMap::new(),
+        }
+    }
+
+    /// a route is used for forwarding if it is reachable
+    fn in_use(route: &Route<T>) -> bool {
+        route.metric != INF && !route.retracted
+    }
+
+    /// checks every invariant over the given routers, returns the violations that were found
+    pub fn check<'a>(&mut self, routers: impl IntoIterator<Item = &'a Router<T>>) -> Vec<Violation<T>> where T: 'a {
+        let routers: Vec<&Router<T>> = routers.into_iter().collect();
+        let mut violations = Vec::new();
+
+        for router in &routers {
+            for (dest, route) in &router.routes {
+                // feasibility distances only decrease, until the seqno increases
+                let seqno = route.source.data().seqno;
+                let key = (router.address.clone(), dest.clone());
+                if let Some((old_seqno, old_fd)) = self.distances.get(&key) {
+                    if seqno_less_than(seqno, *old_seqno) {
+                        violations.push(Violation::SeqnoDecreased {
+                            node: router.address.clone(),
+                            dest: dest.clone(),
+                            old_seqno: *old_seqno,
+                            new_seqno: seqno,
+                        });
+                    } else if seqno == *old_seqno && route.fd > *old_fd {
+                        violations.push(Violation::FeasibilityDistanceIncreased {
+                            node: router.address.clone(),
+                            dest: dest.clone(),
+                            seqno,
+                            old_fd: *old_fd,
+                            new_fd: route.fd,
+                        });
+                    }
+                }
+                self.distances.insert(key, (seqno, route.fd));
+
+                // routes in use must go over a working link
+                if Self::in_use(route) && router.links.get(&route.link).is_none_or(|x| x.metric == INF) {
+                    violations.push(Violation::MissingLink {
+                        node: router.address.clone(),
+                        dest: dest.clone(),
+                        link: route.link.clone(),
+                    });
+                }
+            }
+        }
+
+        let mut dests = RootSet::new();
+        for router in &routers {
+            dests.extend(router.routes.keys().cloned());
+        }
+        for dest in dests {
+            violations.extend(Self::find_loops(&routers, &dest));
+        }
+        violations
+    }
+
+    /// finds every forwarding loop towards dest, each loop is reported once
+    fn find_loops(routers: &[&Router<T>], dest: &T::NodeAddress) -> Vec<Violation<T>> {
+        let next_hop = |node: &T::NodeAddress| -> Option<&T::NodeAddress> {
+            let router = routers.iter().find(|r| r.address == *node)?;
+            router.routes.get(dest).filter(|x| Self::in_use(x)).map(|x| &x.next_hop)
+        };
+
+        let mut violations = Vec::new();
+        let mut visited = RootSet::new();
+        for start in routers.iter().map(|r| &r.address) {
+            let mut path: Vec<T::NodeAddress> = Vec::new();
+            let mut cur = Some(start);
+            while let Some(node) = cur {
+                if *node == *dest || visited.contains(node) {
+                    break;
+                }
+                if let Some(pos) = path.iter().position(|x| x == node) {
+                    // the loop is the part of the path that starts at the repeated router
+                    let mut cycle = path[pos..].to_vec();
+                    cycle.push(node.clone());
+                    violations.push(Violation::ForwardingLoop { dest: dest.clone(), path: cycle });
+                    break;
+                }
+                path.push(node.clone());
+                cur = next_hop(node);
+            }
+            visited.extend(path);
+        }
+        violations
+    }
+}
@@ -0,0 +1,329 @@
+use educe::Educe;
+use serde::{Deserialize, Serialize};
+use crate::concepts::neighbour::Neighbour;
+use crate::concepts::packet::Packet;
+use crate::concepts::route::Route;
+use crate::feedback::{ConsistencyViolation, RoutingError};
+use crate::framework::{MAC, RoutingSystem};
+use crate::router::{FrozenRouter, Router, ThawedRouter};
+
+pub mod event;
+pub mod invariants;
+pub mod model;
+pub mod oracle;
+
+/// A packet that is travelling over a link of the simulated network
+#[derive(Educe)]
+#[educe(Clone(bound()))]
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct InFlightPacket<T: RoutingSystem + ?Sized> {
+    /// the router that will receive this packet
+    pub dest: T::NodeAddress,
+    /// the link the packet was sent over
+    pub link: T::Link,
+    pub packet: MAC<Packet<T>, T>,
+    /// the tick in which this packet is delivered
+    pub deliver_at: u64,
+}
+
+/// Faults that are applied to every packet sent over a link
+#[derive(Clone, Serialize, Deserialize)]
+pub struct LinkFaults {
+    /// probability that a packet is lost
+    pub loss: f64,
+    /// number of ticks it takes for a packet to arrive, at least 1
+    pub latency: u64,
+    /// probability that a packet is delivered twice
+    pub duplication: f64,
+    /// probability that a packet is held back for an extra tick, letting packets sent after it arrive first
+    pub reordering: f64,
+}
+
+impl Default for LinkFaults {
+    fn default() -> Self {
+        Self {
+            loss: 0.0,
+            latency: 1,
+            duplication: 0.0,
+            reordering: 0.0,
+        }
+    }
+}
+
+/// A small seeded random number generator (SplitMix64), so that lossy simulations can be reproduced exactly
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct SimRng {
+    state: u64,
+}
+
+impl SimRng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E3779B97F4A7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z ^ (z >> 31)
+    }
+
+    /// returns true with the given probability
+    pub fn chance(&mut self, probability: f64) -> bool {
+        probability > 0.0 && ((self.next_u64() >> 11) as f64 / (1u64 << 53) as f64) < probability
+    }
+}
+
+/// A bidirectional link between two simulated routers, both routers refer to it with the same `Link`
+#[derive(Educe)]
+#[educe(Clone(bound()))]
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct SimLink<T: RoutingSystem + ?Sized> {
+    pub link: T::Link,
+    pub a: T::NodeAddress,
+    pub b: T::NodeAddress,
+    /// metric of the link in both directions, INF if the link is down
+    pub metric: u16,
+    pub faults: LinkFaults,
+}
+
+/// An in-memory network of routers that exchange packets in lock-step ticks.
+///
+/// Every tick, the packets that are due are delivered, then every router performs a full update.
+/// By default, packets arrive in the tick after they were sent, [LinkFaults] can make links lossy or slow.
+/// Links may point to nodes that are not part of the simulation, packets sent to them are lost.
+pub struct Simulation<T: RoutingSystem> {
+    pub routers: Vec<Router<T>>,
+    pub links: Vec<SimLink<T>>,
+    /// packets that are travelling over the network, in the order they were sent
+    pub packets: Vec<InFlightPacket<T>>,
+    /// the current tick
+    pub time: u64,
+    /// decides which faults happen, seed this to reproduce a lossy simulation
+    pub rng: SimRng,
+    /// errors returned by the routers while handling packets, drain this regularly
+    pub errors: Vec<(T::NodeAddress, RoutingError<T>)>,
+    /// inconsistencies found in the routers after each tick, see [Router::check_invariants]
+    pub violations: Vec<(T::NodeAddress, ConsistencyViolation<T>)>,
+}
+
+#[derive(Serialize)]
+#[serde(bound = "")]
+struct FrozenSimulation<'a, T: RoutingSystem> {
+    routers: Vec<FrozenRouter<'a, T>>,
+    links: &'a Vec<SimLink<T>>,
+    packets: &'a Vec<InFlightPacket<T>>,
+    time: u64,
+    rng: &'a SimRng,
+}
+
+#[derive(Deserialize)]
+#[serde(bound = "")]
+struct ThawedSimulation<T: RoutingSystem> {
+    routers: Vec<ThawedRouter<T>>,
+    links: Vec<SimLink<T>>,
+    packets: Vec<InFlightPacket<T>>,
+    time: u64,
+    rng: SimRng,
+}
+
+impl<T: RoutingSystem> Default for Simulation<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: RoutingSystem> Simulation<T> {
+    pub fn new() -> Self {
+        Self {
+            routers: Vec::new(),
+            links: Vec::new(),
+            packets: Vec::new(),
+            time: 0,
+            rng: SimRng::default(),
+            errors: Vec::new(),
+            violations: Vec::new(),
+        }
+    }
+
+    /// seeds the faults, runs with the same seed are identical
+    pub fn set_seed(&mut self, seed: u64) -> &mut Self {
+        self.rng = SimRng::new(seed);
+        self
+    }
+
+    // region Topology
+
+    /// adds a router with no links to the network
+    pub fn add_router(&mut self, addr: T::NodeAddress) -> &mut Self {
+        self.routers.push(Router::new(addr));
+        self
+    }
+
+    /// connects a and b with a bidirectional link
+    pub fn add_link(&mut self, link: T::Link, a: T::NodeAddress, b: T::NodeAddress, metric: u16) -> &mut Self {
+        for (node, neigh) in [(&a, &b), (&b, &a)] {
+            if let Some(router) = self.router_mut(node) {
+                let mut neighbour = Neighbour::new(neigh.clone());
+                neighbour.metric = metric;
+                router.links.insert(link.clone(), neighbour);
+            }
+        }
+        self.links.push(SimLink { link, a, b, metric, faults: LinkFaults::default() });
+        self
+    }
+
+    /// sets the faults of a link, in both directions
+    pub fn set_link_faults(&mut self, link: &T::Link, faults: LinkFaults) -> &mut Self {
+        for sim_link in self.links.iter_mut().filter(|x| x.link == *link) {
+            sim_link.faults = faults.clone();
+        }
+        self
+    }
+
+    /// sets the faults of every link, in both directions
+    pub fn set_all_link_faults(&mut self, faults: LinkFaults) -> &mut Self {
+        for sim_link in &mut self.links {
+            sim_link.faults = faults.clone();
+        }
+        self
+    }
+
+    /// changes the metric of a link on both of its ends
+    pub fn set_link_metric(&mut self, link: &T::Link, metric: u16) {
+        for sim_link in self.links.iter_mut().filter(|x| x.link == *link) {
+            sim_link.metric = metric;
+        }
+        for router in &mut self.routers {
+            router.set_link_metric(link, metric);
+        }
+    }
+
+    /// removes a link from both of its ends, packets that are in flight over it are lost
+    pub fn remove_link(&mut self, link: &T::Link) {
+        self.links.retain(|x| x.link != *link);
+        for router in &mut self.routers {
+            router.links.remove(link);
+        }
+    }
+
+    // endregion
+
+    // region Inspection
+
+    pub fn router(&self, addr: &T::NodeAddress) -> Option<&Router<T>> {
+        self.routers.iter().find(|r| r.address == *addr)
+    }
+
+    pub fn router_mut(&mut self, addr: &T::NodeAddress) -> Option<&mut Router<T>> {
+        self.routers.iter_mut().find(|r| r.address == *addr)
+    }
+
+    /// the route to dest in the table of node
+    pub fn route(&self, node: &T::NodeAddress, dest: &T::NodeAddress) -> Option<&Route<T>> {
+        self.router(node)?.routes.get(dest)
+    }
+
+    // endregion
+
+    // region Stepping
+
+    /// moves the outbound packets of every router onto the network, applying the faults of their links
+    pub fn collect_packets(&mut self) {
+        let no_faults = LinkFaults::default();
+        for router in &mut self.routers {
+            for packet in router.take_outbound_packets() {
+                let faults = self.links.iter()
+                    .find(|x| x.link == packet.link)
+                    .map_or(&no_faults, |x| &x.faults);
+                if self.rng.chance(faults.loss) {
+                    continue;
+                }
+                let copies = if self.rng.chance(faults.duplication) { 2 } else { 1 };
+                for _ in 0..copies {
+                    let mut deliver_at = self.time + faults.latency.max(1);
+                    if self.rng.chance(faults.reordering) {
+                        deliver_at += 1;
+                    }
+                    self.packets.push(InFlightPacket {
+                        dest: packet.dest.clone(),
+                        link: packet.link.clone(),
+                        packet: packet.packet.clone(),
+                        deliver_at,
+                    });
+                }
+            }
+        }
+    }
+
+    /// hands every packet that is due to its destination
+    pub fn deliver_packets(&mut self) {
+        let (due, pending) = std::mem::take(&mut self.packets)
+            .into_iter()
+            .partition(|x| x.deliver_at <= self.time);
+        self.packets = pending;
+        for InFlightPacket { dest, link, packet, .. } in due {
+            if let Some(router) = self.routers.iter_mut().find(|r| r.address == dest) {
+                // the link may have been removed while the packet was in flight
+                if let Some(neigh_addr) = router.links.get(&link).map(|x| x.addr.clone()) {
+                    if let Err(err) = router.handle_packet(&packet, &link, &neigh_addr) {
+                        self.errors.push((dest, err));
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn tick(&mut self) {
+        self.time += 1;
+        self.deliver_packets();
+        for router in &mut self.routers {
+            router.full_update();
+            for violation in router.check_invariants() {
+                self.violations.push((router.address.clone(), violation));
+            }
+        }
+        self.collect_packets();
+    }
+
+    pub fn tick_n(&mut self, times: usize) {
+        for _ in 0..times {
+            self.tick();
+        }
+    }
+
+    // endregion
+
+    // region Persistence
+
+    /// serializes the whole network, including the routing tables and the packets in flight
+    pub fn freeze(&self) -> String {
+        let frozen = FrozenSimulation {
+            routers: self.routers.iter().map(FrozenRouter::new).collect(),
+            links: &self.links,
+            packets: &self.packets,
+            time: self.time,
+            rng: &self.rng,
+        };
+        serde_json::to_string(&frozen).expect("Failed to serialize the simulation")
+    }
+
+    /// restores a network that was serialized with freeze
+    pub fn restore(state: &str) -> serde_json::Result<Self> {
+        let thawed: ThawedSimulation<T> = serde_json::from_str(state)?;
+        Ok(Self {
+            routers: thawed.routers.into_iter().map(ThawedRouter::thaw).collect(),
+            links: thawed.links,
+            packets: thawed.packets,
+            time: thawed.time,
+            rng: thawed.rng,
+            errors: Vec::new(),
+            violations: Vec::new(),
+        })
+    }
+
+    // endregion
+}
@@ -0,0 +1,349 @@
+use std::collections::{HashSet, VecDeque};
+use educe::Educe;
+use crate::concepts::neighbour::NeighbourProtocol;
+use crate::concepts::packet::{Capabilities, PROTOCOL_VERSION};
+use crate::feedback::RoutingError;
+use crate::framework::RoutingSystem;
+use crate::sim::invariants::{InvariantChecker, Violation};
+use crate::sim::oracle::{RouteMismatch, ShortestPaths};
+use crate::sim::{InFlightPacket, Simulation};
+
+/// A single nondeterministic choice made by the model checker
+#[derive(Educe)]
+#[educe(Debug, Clone(bound()))]
+pub enum Step<T: RoutingSystem + ?Sized> {
+    /// a packet in flight is delivered, and the router runs an update
+    Deliver {
+        dest: T::NodeAddress,
+        link: T::Link,
+    },
+    /// a packet in flight is lost
+    Drop {
+        dest: T::NodeAddress,
+        link: T::Link,
+    },
+    /// a router runs a full update
+    FullUpdate {
+        node: T::NodeAddress,
+    },
+    /// the metric of a link changes on both of its ends, and both routers run an update
+    SetLinkMetric {
+        link: T::Link,
+        metric: u16,
+    },
+}
+
+/// Why a state explored by the model checker is wrong
+#[derive(Educe)]
+#[educe(Debug, Clone(bound()))]
+pub enum ModelFailure<T: RoutingSystem + ?Sized> {
+    /// an invariant was broken by the last step of the trace
+    Invariant(Violation<T>),
+    /// the network did not converge to the shortest paths after the trace, once no more faults happened
+    NotConverged(Vec<RouteMismatch<T>>),
+    /// a router returned an error while handling a packet, in the last step of the trace or while settling
+    RouterError(T::NodeAddress, RoutingError<T>),
+}
+
+/// A sequence of steps from the starting state that leads to a failure
+#[derive(Educe)]
+#[educe(Debug, Clone(bound()))]
+pub struct Counterexample<T: RoutingSystem + ?Sized> {
+    pub trace: Vec<Step<T>>,
+    pub failure: ModelFailure<T>,
+}
+
+#[derive(Educe)]
+#[educe(Debug)]
+pub struct ModelReport<T: RoutingSystem + ?Sized> {
+    /// number of distinct states that were explored
+    pub states: usize,
+    /// false if the exploration stopped at max_states, before every reachable state was explored
+    pub complete: bool,
+    /// the shortest trace that breaks loop-freedom or convergence, or makes a router return an error, if any
+    pub counterexample: Option<Counterexample<T>>,
+}
+
+/// a step, the state it leads to, and the updates, losses and link changes that are left
+type Successor<T> = (Step<T>, Simulation<T>, usize, usize, Vec<bool>);
+
+/// A state waiting to be explored
+struct Pending<T: RoutingSystem> {
+    state: String,
+    trace: Vec<Step<T>>,
+    updates_left: usize,
+    losses_left: usize,
+    /// which of the link changes have been applied
+    applied: Vec<bool>,
+}
+
+/// a serialized state together with the remaining bounds
+type StateKey = (String, usize, usize, Vec<bool>);
+
+impl<T: RoutingSystem> Pending<T> {
+    /// identifies the state together with the remaining bounds, the whole state is compared, so distinct states are never merged
+    fn key(&self) -> StateKey {
+        (self.state.clone(), self.updates_left, self.losses_left, self.applied.clone())
+    }
+}
+
+/// Explores every interleaving of packet deliveries, packet losses, full updates and link metric changes
+/// from a starting network, up to the given bounds.
+///
+/// After every step, the checker looks for router errors, forwarding loops and for feasibility distances or seqnos that went the
+/// wrong way. Whenever no packets are in flight, it also lets the network run without faults for `settle_ticks`
+/// ticks, and checks that every router ends up on a shortest path.
+///
+/// States are compared by their serialized form, so this is only practical for a handful of routers.
+/// [LinkFaults](super::LinkFaults) and packet delivery times are ignored, the checker decides what happens to every packet.
+pub struct ModelChecker<T: RoutingSystem> {
+    /// maximum number of full updates in a trace
+    pub max_updates: usize,
+    /// maximum number of lost packets in a trace
+    pub max_losses: usize,
+    /// the exploration stops after this many distinct states
+    pub max_states: usize,
+    /// allow packets sent over the same link in the same direction to arrive out of order.
+    /// When false, links behave like a stream, such as TCP, which keeps the state space much smaller.
+    pub reordering: bool,
+    /// number of fault-free ticks after which a quiet network must have converged
+    pub settle_ticks: usize,
+    /// link metric changes that may happen at any point of a trace, each one at most once
+    pub link_changes: Vec<(T::Link, u16)>,
+}
+
+impl<T: RoutingSystem> Default for ModelChecker<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: RoutingSystem> ModelChecker<T> {
+    pub fn new() -> Self {
+        Self {
+            max_updates: 2,
+            max_losses: 1,
+            max_states: 100_000,
+            reordering: false,
+            settle_ticks: 20,
+            link_changes: Vec::new(),
+        }
+    }
+
+    /// checks every state reachable from start, stopping at the first counterexample.
+    /// Hellos are not explored, every router starts out knowing the protocol of its neighbours.
+    pub fn run(&self, start: &Simulation<T>) -> ModelReport<T> {
+        let mut start = Simulation::restore(&start.freeze()).expect("Failed to copy the simulation");
+        for router in &mut start.routers {
+            for neigh in router.links.values_mut() {
+                neigh.protocol = NeighbourProtocol::Known {
+                    version: PROTOCOL_VERSION,
+                    capabilities: Capabilities::ALL,
+                };
+            }
+        }
+        Self::collect(&mut start);
+
+        let initial = Pending {
+            state: self.normalize(&mut start),
+            trace: Vec::new(),
+            updates_left: self.max_updates,
+            losses_left: self.max_losses,
+            applied: vec![false; self.link_changes.len()],
+        };
+        let mut seen = HashSet::new();
+        seen.insert(initial.key());
+        let mut queue = VecDeque::new();
+        queue.push_back(initial);
+
+        let mut states = 0;
+        while let Some(pending) = queue.pop_front() {
+            if states == self.max_states {
+                return ModelReport { states, complete: false, counterexample: None };
+            }
+            states += 1;
+
+            let sim = Self::thaw(&pending.state);
+            if sim.packets.is_empty() {
+                if let Some(failure) = self.settle(&sim) {
+                    return ModelReport {
+                        states,
+                        complete: false,
+                        counterexample: Some(Counterexample { trace: pending.trace, failure }),
+                    };
+                }
+            }
+
+            for (step, mut next, updates_left, losses_left, applied) in self.successors(&sim, &pending) {
+                let mut trace = pending.trace.clone();
+                trace.push(step);
+
+                if let Some((node, error)) = next.errors.first().cloned() {
+                    return ModelReport {
+                        states,
+                        complete: false,
+                        counterexample: Some(Counterexample { trace, failure: ModelFailure::RouterError(node, error) }),
+                    };
+                }
+                let mut checker = InvariantChecker::new();
+                checker.check(&sim.routers);
+                if let Some(violation) = checker.check(&next.routers).into_iter().next() {
+                    return ModelReport {
+                        states,
+                        complete: false,
+                        counterexample: Some(Counterexample { trace, failure: ModelFailure::Invariant(violation) }),
+                    };
+                }
+
+                let pending = Pending {
+                    state: self.normalize(&mut next),
+                    trace,
+                    updates_left,
+                    losses_left,
+                    applied,
+                };
+                if seen.insert(pending.key()) {
+                    queue.push_back(pending);
+                }
+            }
+        }
+        ModelReport { states, complete: true, counterexample: None }
+    }
+
+    /// every state that can be reached from sim in one step
+    fn successors(&self, sim: &Simulation<T>, pending: &Pending<T>) -> Vec<Successor<T>> {
+        let mut next = Vec::new();
+
+        for (idx, packet) in sim.packets.iter().enumerate() {
+            let earlier = &sim.packets[..idx];
+            if self.reordering {
+                // identical packets lead to identical states, only consider the first one
+                let serialized = Self::packet_key(packet, true);
+                if earlier.iter().any(|x| Self::packet_key(x, true) == serialized) {
+                    continue;
+                }
+            } else if earlier.iter().any(|x| x.dest == packet.dest && x.link == packet.link) {
+                continue; // only the oldest packet on each link can arrive
+            }
+            let mut delivered = Self::thaw(&pending.state);
+            let InFlightPacket { dest, link, packet: data, .. } = delivered.packets.remove(idx);
+            if let Some(router) = delivered.routers.iter_mut().find(|r| r.address == dest) {
+                if let Some(neigh_addr) = router.links.get(&link).map(|x| x.addr.clone()) {
+                    if let Err(err) = router.handle_packet(&data, &link, &neigh_addr) {
+                        delivered.errors.push((dest.clone(), err));
+                    }
+                    router.update();
+                }
+            }
+            Self::collect(&mut delivered);
+            let step = Step::Deliver { dest: packet.dest.clone(), link: packet.link.clone() };
+            next.push((step, delivered, pending.updates_left, pending.losses_left, pending.applied.clone()));
+
+            if pending.losses_left > 0 {
+                let mut dropped = Self::thaw(&pending.state);
+                dropped.packets.remove(idx);
+                let step = Step::Drop { dest: packet.dest.clone(), link: packet.link.clone() };
+                next.push((step, dropped, pending.updates_left, pending.losses_left - 1, pending.applied.clone()));
+            }
+        }
+
+        if pending.updates_left > 0 {
+            for idx in 0..sim.routers.len() {
+                let mut updated = Self::thaw(&pending.state);
+                updated.routers[idx].full_update();
+                Self::collect(&mut updated);
+                let step = Step::FullUpdate { node: sim.routers[idx].address.clone() };
+                next.push((step, updated, pending.updates_left - 1, pending.losses_left, pending.applied.clone()));
+            }
+        }
+
+        for (idx, (link, metric)) in self.link_changes.iter().enumerate() {
+            if pending.applied[idx] {
+                continue;
+            }
+            let mut changed = Self::thaw(&pending.state);
+            changed.set_link_metric(link, *metric);
+            for router in changed.routers.iter_mut().filter(|r| r.links.contains_key(link)) {
+                router.update();
+            }
+            Self::collect(&mut changed);
+            let mut applied = pending.applied.clone();
+            applied[idx] = true;
+            let step = Step::SetLinkMetric { link: link.clone(), metric: *metric };
+            next.push((step, changed, pending.updates_left, pending.losses_left, applied));
+        }
+        next
+    }
+
+    /// runs the network without faults, and checks that it stays loop-free and converges
+    fn settle(&self, sim: &Simulation<T>) -> Option<ModelFailure<T>> {
+        let mut sim = Self::thaw(&sim.freeze());
+        let mut checker = InvariantChecker::new();
+        checker.check(&sim.routers);
+        for _ in 0..self.settle_ticks {
+            sim.time += 1;
+            sim.deliver_packets();
+            for router in &mut sim.routers {
+                router.full_update();
+            }
+            Self::collect(&mut sim);
+            if let Some((node, error)) = sim.errors.first().cloned() {
+                return Some(ModelFailure::RouterError(node, error));
+            }
+            if let Some(violation) = checker.check(&sim.routers).into_iter().next() {
+                return Some(ModelFailure::Invariant(violation));
+            }
+        }
+        let mismatches = ShortestPaths::from_simulation(&sim).check(&sim.routers);
+        if mismatches.is_empty() {
+            None
+        } else {
+            Some(ModelFailure::NotConverged(mismatches))
+        }
+    }
+
+    /// puts the outbound packets of every router in flight, without applying link faults
+    fn collect(sim: &mut Simulation<T>) {
+        for router in &mut sim.routers {
+            for packet in router.take_outbound_packets() {
+                sim.packets.push(InFlightPacket {
+                    dest: packet.dest,
+                    link: packet.link,
+                    packet: packet.packet,
+                    deliver_at: sim.time + 1,
+                });
+            }
+        }
+    }
+
+    /// sorting packets by this key keeps the packets sent over the same link in order, unless reordering is allowed
+    fn packet_key(packet: &InFlightPacket<T>, reordering: bool) -> String {
+        let key = if reordering {
+            serde_json::to_string(packet)
+        } else {
+            serde_json::to_string(&(&packet.dest, &packet.link))
+        };
+        key.expect("Failed to serialize a packet")
+    }
+
+    /// serializes a state, leaving out the parts that do not affect routing, errors were already reported by then
+    fn normalize(&self, sim: &mut Simulation<T>) -> String {
+        sim.time = 0;
+        // packets on different links can arrive in any order, so only the order within each link matters
+        for packet in &mut sim.packets {
+            packet.deliver_at = 1;
+        }
+        let reordering = self.reordering;
+        sim.packets.sort_by_cached_key(|x| Self::packet_key(x, reordering));
+        sim.errors.clear();
+        sim.violations.clear();
+        for router in &mut sim.routers {
+            router.warnings.clear();
+        }
+        sim.freeze()
+    }
+
+    fn thaw(state: &str) -> Simulation<T> {
+        Simulation::restore(state).expect("Failed to restore a state")
+    }
+}
@@ -0,0 +1,156 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fmt::Debug;
+use educe::Educe;
+use crate::framework::RoutingSystem;
+use crate::router::{Router, INF};
+use crate::sim::event::EventSimulation;
+use crate::sim::Simulation;
+use crate::util::{sum_inf, RootMap, RootSet};
+
+/// A route that does not match the shortest path through the network
+#[derive(Educe)]
+#[educe(Debug, Clone(bound()))]
+pub struct RouteMismatch<T: RoutingSystem + ?Sized> {
+    pub node: T::NodeAddress,
+    pub dest: T::NodeAddress,
+    /// metric of the shortest path, INF if dest is unreachable
+    pub expected_metric: u16,
+    /// metric of the route in the node's table, INF if there is no route
+    pub actual_metric: u16,
+    /// next hop of the route in the node's table, if any
+    pub actual_next_hop: Option<T::NodeAddress>,
+}
+
+/// Shortest paths between every pair of routers, computed with Dijkstra's algorithm over the link graph.
+///
+/// Metrics are summed like root does, saturating at INF - 1.
+pub struct ShortestPaths<T: RoutingSystem + ?Sized> {
+    /// node -> (neighbour, metric)
+    edges: RootMap<T::NodeAddress, Vec<(T::NodeAddress, u16)>>,
+    /// (node, dest) -> metric of the shortest path
+    distances: RootMap<(T::NodeAddress, T::NodeAddress), u16>,
+}
+
+impl<T: RoutingSystem> ShortestPaths<T> {
+    /// computes the shortest paths between nodes, given every bidirectional link as (a, b, metric).
+    /// Links that are down (INF) are ignored.
+    pub fn new<'a>(nodes: impl IntoIterator<Item = &'a T::NodeAddress>, links: impl IntoIterator<Item = (&'a T::NodeAddress, &'a T::NodeAddress, u16)>) -> Self where T: 'a {
+        let nodes: RootSet<T::NodeAddress> = nodes.into_iter().cloned().collect();
+        let mut edges: RootMap<T::NodeAddress, Vec<(T::NodeAddress, u16)>> = RootMap::new();
+        for (a, b, metric) in links {
+            if metric == INF || !nodes.contains(a) || !nodes.contains(b) {
+                continue;
+            }
+            // root treats a zero metric as 1
+            let metric = metric.max(1);
+            edges.entry(a.clone()).or_default().push((b.clone(), metric));
+            edges.entry(b.clone()).or_default().push((a.clone(), metric));
+        }
+
+        let mut paths = Self {
+            edges,
+            distances: RootMap::new(),
+        };
+        let order: Vec<T::NodeAddress> = nodes.iter().cloned().collect();
+        let index: RootMap<T::NodeAddress, usize> = order.iter().cloned().enumerate().map(|(idx, addr)| (addr, idx)).collect();
+        for dest in &nodes {
+            paths.dijkstra(&order, &index, dest);
+        }
+        paths
+    }
+
+    pub fn from_simulation(sim: &Simulation<T>) -> Self {
+        Self::new(
+            sim.routers.iter().map(|r| &r.address),
+            sim.links.iter().map(|x| (&x.a, &x.b, x.metric)),
+        )
+    }
+
+    pub fn from_event_simulation(sim: &EventSimulation<T>) -> Self {
+        Self::new(
+            sim.routers.iter().map(|r| &r.address),
+            sim.links.iter().map(|x| (&x.a, &x.b, x.metric)),
+        )
+    }
+
+    /// fills in the distance from every node to dest, links are symmetric so we can search from dest
+    fn dijkstra(&mut self, order: &[T::NodeAddress], index: &RootMap<T::NodeAddress, usize>, dest: &T::NodeAddress) {
+        let mut best: Vec<Option<u16>> = vec![None; order.len()];
+        let mut queue = BinaryHeap::new();
+        if let Some(idx) = index.get(dest) {
+            queue.push(Reverse((0u16, *idx)));
+        }
+        while let Some(Reverse((metric, idx))) = queue.pop() {
+            if best[idx].is_some() {
+                continue;
+            }
+            best[idx] = Some(metric);
+            for (neigh, link_metric) in self.edges.get(&order[idx]).into_iter().flatten() {
+                if let Some(&n_idx) = index.get(neigh) {
+                    if best[n_idx].is_none() {
+                        queue.push(Reverse((sum_inf(metric, *link_metric), n_idx)));
+                    }
+                }
+            }
+        }
+        for (idx, metric) in best.into_iter().enumerate() {
+            if let Some(metric) = metric {
+                self.distances.insert((order[idx].clone(), dest.clone()), metric);
+            }
+        }
+    }
+
+    /// metric of the shortest path from node to dest, INF if there is none
+    pub fn metric(&self, node: &T::NodeAddress, dest: &T::NodeAddress) -> u16 {
+        self.distances.get(&(node.clone(), dest.clone())).copied().unwrap_or(INF)
+    }
+
+    /// every neighbour of node that lies on a shortest path to dest
+    pub fn next_hops(&self, node: &T::NodeAddress, dest: &T::NodeAddress) -> RootSet<T::NodeAddress> {
+        let metric = self.metric(node, dest);
+        let mut hops = RootSet::new();
+        if metric == INF || node == dest {
+            return hops;
+        }
+        for (neigh, link_metric) in self.edges.get(node).into_iter().flatten() {
+            if sum_inf(*link_metric, self.metric(neigh, dest)) == metric {
+                hops.insert(neigh.clone());
+            }
+        }
+        hops
+    }
+
+    /// compares the routing table of every router with the shortest paths. Any shortest next hop is accepted.
+    pub fn check<'a>(&self, routers: impl IntoIterator<Item = &'a Router<T>>) -> Vec<RouteMismatch<T>> where T: 'a {
+        let routers: Vec<&Router<T>> = routers.into_iter().collect();
+        let mut mismatches = Vec::new();
+        for router in &routers {
+            for dest in routers.iter().map(|r| &r.address).filter(|x| **x != router.address) {
+                let expected_metric = self.metric(&router.address, dest);
+                let route = router.routes.get(dest).filter(|x| x.metric != INF);
+                let matches = match route {
+                    None => expected_metric == INF,
+                    Some(route) => route.metric == expected_metric
+                        && self.next_hops(&router.address, dest).contains(&route.next_hop),
+                };
+                if !matches {
+                    mismatches.push(RouteMismatch {
+                        node: router.address.clone(),
+                        dest: dest.clone(),
+                        expected_metric,
+                        actual_metric: route.map_or(INF, |x| x.metric),
+                        actual_next_hop: route.map(|x| x.next_hop.clone()),
+                    });
+                }
+            }
+        }
+        mismatches
+    }
+
+    /// panics if any router does not use a shortest path
+    pub fn assert_optimal<'a>(&self, routers: impl IntoIterator<Item = &'a Router<T>>) where T: 'a, T::NodeAddress: Debug {
+        let mismatches = self.check(routers);
+        assert!(mismatches.is_empty(), "{} routes are not shortest paths: {:?}", mismatches.len(), mismatches);
+    }
+}
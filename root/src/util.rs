@@ -1,6 +1,12 @@
 use crate::router::INF;
 use std::cmp::min;
 
+/// Map used for all of root's internal state. It is ordered by key,
+/// so links, sources and outbound packets are always visited in the same order.
+pub type RootMap<K, V> = std::collections::BTreeMap<K, V>;
+/// Set used for all of root's internal state, see [RootMap]
+pub type RootSet<K> = std::collections::BTreeSet<K>;
+
 /// Compares whether a < b mod 65536
 ///
 /// # Arguments
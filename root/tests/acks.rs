@@ -0,0 +1,159 @@
+use root::concepts::packet::{OutboundPacket, Packet};
+use root::feedback::RoutingWarning;
+use root::router::{Router, INF};
+use root::sim::Simulation;
+
+mod common;
+use common::NetworkExt;
+
+common::test_system!(AckSystem {
+    const REQUEST_ACKS: bool = true;
+    const MAX_RETRANSMISSIONS: u32 = 2;
+});
+
+/// 1 - 2 - 3, as separate routers that only exchange the packets we deliver
+struct Line{
+    routers: Vec<Router<AckSystem>>,
+}
+
+impl Line{
+    fn new() -> Self{
+        let mut routers = Vec::new();
+        for (addr, links) in [("1", vec![(0, "2")]), ("2", vec![(0, "1"), (1, "3")]), ("3", vec![(1, "2")])]{
+            routers.push(common::router(addr, &links));
+        }
+        let mut line = Self{ routers };
+        for _ in 0..5{
+            line.round();
+        }
+        line
+    }
+
+    fn router(&mut self, addr: &str) -> &mut Router<AckSystem>{
+        self.routers.iter_mut().find(|x| x.address == addr).unwrap()
+    }
+
+    fn deliver(&mut self, from: &str, packets: Vec<OutboundPacket<AckSystem>>){
+        for OutboundPacket{link, dest, packet} in packets{
+            let router = self.router(&dest);
+            router.handle_packet(&packet, &link, &from.to_string()).unwrap();
+            router.update();
+        }
+    }
+
+    /// delivers every packet until the network is quiet
+    fn flush(&mut self){
+        loop{
+            let mut sent = false;
+            for i in 0..self.routers.len(){
+                let from = self.routers[i].address.clone();
+                let packets = self.routers[i].take_outbound_packets();
+                sent |= !packets.is_empty();
+                self.deliver(&from, packets);
+            }
+            if !sent{
+                break;
+            }
+        }
+    }
+
+    fn round(&mut self){
+        for router in &mut self.routers{
+            router.full_update();
+        }
+        self.flush();
+    }
+
+    /// breaks the link between 2 and 3, and returns the urgent updates 2 sends to 1
+    fn break_link(&mut self) -> Vec<OutboundPacket<AckSystem>>{
+        self.router("3").set_link_metric(&1, INF);
+        let router = self.router("2");
+        router.set_link_metric(&1, INF);
+        router.update();
+        router.take_outbound_packets().into_iter().filter(|x| x.dest == "1").collect()
+    }
+}
+
+#[test]
+fn urgent_updates_request_acks(){
+    let mut line = Line::new();
+    let packets = line.break_link();
+    assert_eq!(packets.len(), 1);
+    assert!(matches!(&packets[0].packet.data, Packet::AckRequest { packet, .. } if matches!(packet.data, Packet::UrgentRouteUpdate(_))));
+
+    line.deliver("2", packets);
+    let acks: Vec<_> = line.router("1").take_outbound_packets().into_iter()
+        .filter(|x| matches!(x.packet.data, Packet::Ack { .. }))
+        .collect();
+    assert_eq!(acks.len(), 1);
+    assert_eq!(acks[0].dest, "2");
+    line.deliver("1", acks);
+
+    // nothing is left to retransmit
+    let router = line.router("2");
+    router.retransmit();
+    assert!(router.take_outbound_packets().is_empty());
+}
+
+#[test]
+fn lost_updates_are_retransmitted(){
+    let mut line = Line::new();
+    let lost = line.break_link();
+    assert!(!lost.is_empty());
+    assert_eq!(line.router("1").routes[&"3".to_string()].metric, 2);
+
+    let router = line.router("2");
+    router.retransmit();
+    let packets: Vec<_> = router.take_outbound_packets().into_iter().filter(|x| x.dest == "1").collect();
+    assert_eq!(packets.len(), 1);
+    line.deliver("2", packets);
+    assert_eq!(line.router("1").routes[&"3".to_string()].metric, INF);
+    line.flush();
+
+    let router = line.router("2");
+    router.retransmit();
+    assert!(router.take_outbound_packets().is_empty());
+}
+
+#[test]
+fn silent_neighbour_is_reported(){
+    let mut line = Line::new();
+    line.break_link();
+    let router = line.router("2");
+    router.warnings.clear();
+    for _ in 0..5{
+        router.retransmit();
+        router.take_outbound_packets();
+    }
+    let warnings: Vec<_> = router.warnings.drain(..).collect();
+    assert_eq!(warnings.len(), 1);
+    assert!(matches!(&warnings[0], RoutingWarning::NotAcknowledging{ link: 0, neighbour } if neighbour == "1"));
+    // the updates were dropped
+    router.retransmit();
+    assert!(router.take_outbound_packets().is_empty());
+}
+
+#[test]
+fn full_update_supersedes_pending_updates(){
+    let mut line = Line::new();
+    line.break_link();
+    let router = line.router("2");
+    router.full_update();
+    router.take_outbound_packets();
+    router.retransmit();
+    assert!(router.take_outbound_packets().is_empty());
+}
+
+#[test]
+fn converges_with_acks(){
+    let mut network: Simulation<AckSystem> = common::create(
+        &["1", "2", "3", "4"],
+        &[(0, "1", "2", 1), (1, "2", "3", 1), (2, "3", "4", 1), (3, "1", "4", 5)]
+    );
+    network.tick_n(10);
+    network.set_link_metric(&1, INF);
+    network.tick_n(10);
+    assert_eq!(network.get_metric_to("2", "3"), 7);
+    assert!(network.violations.is_empty());
+    assert!(network.errors.is_empty());
+}
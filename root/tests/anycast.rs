@@ -0,0 +1,64 @@
+use root::sim::invariants::InvariantChecker;
+use crate::common::{tick_checked, VirtualNetwork};
+
+mod common;
+use common::NetworkExt;
+
+const SERVICE: &str = "svc";
+
+fn serve(network: &mut VirtualNetwork, node: &str){
+    network.router_mut(&node.to_string()).unwrap().serve_anycast(SERVICE.to_string());
+}
+
+/// 1 - 2 - 3 - 4 - 5 - 6, the service runs on 1 and 6
+fn line() -> VirtualNetwork{
+    let mut network = common::line(6);
+    network.tick_n(5);
+    serve(&mut network, "1");
+    network.tick_n(5);
+    serve(&mut network, "6");
+    network.tick_n(15);
+    network
+}
+
+#[test]
+fn closest_origin_is_selected(){
+    let network = line();
+    assert_eq!(network.reachable_route("2", SERVICE), Some(("1".to_string(), 1)));
+    assert_eq!(network.reachable_route("3", SERVICE), Some(("2".to_string(), 2)));
+    assert_eq!(network.reachable_route("4", SERVICE), Some(("5".to_string(), 2)));
+    assert_eq!(network.reachable_route("5", SERVICE), Some(("6".to_string(), 1)));
+    assert!(network.route(&"1".to_string(), &SERVICE.to_string()).is_none());
+    assert!(network.violations.is_empty());
+    assert!(network.errors.is_empty());
+}
+
+#[test]
+fn origins_share_the_seqno(){
+    let network = line();
+    let seqnos: Vec<_> = ["1", "6"].iter()
+        .map(|node| network.router(&node.to_string()).unwrap().anycast[SERVICE])
+        .collect();
+    assert_eq!(seqnos[0], seqnos[1]);
+}
+
+#[test]
+fn stopped_origin_is_replaced(){
+    let mut network = line();
+    let mut checker = InvariantChecker::new();
+    tick_checked(&mut network, &mut checker, 1);
+    network.router_mut(&"1".to_string()).unwrap().stop_anycast(&SERVICE.to_string());
+    tick_checked(&mut network, &mut checker, 15);
+    assert_eq!(network.reachable_route("1", SERVICE), Some(("2".to_string(), 5)));
+    assert_eq!(network.reachable_route("3", SERVICE), Some(("4".to_string(), 3)));
+    assert!(network.violations.is_empty());
+}
+
+#[test]
+fn failed_origin_is_replaced(){
+    let mut network = line();
+    network.remove_link(&0);
+    network.tick_n(15);
+    assert_eq!(network.reachable_route("2", SERVICE), Some(("3".to_string(), 4)));
+    assert!(network.violations.is_empty());
+}
@@ -0,0 +1,82 @@
+use root::router::INF;
+use root::sim::Simulation;
+use crate::common::{create, VirtualNetwork};
+
+mod common;
+
+common::test_system!(AreaSystem {
+    /// "a1" is in area "a", and "a" is an area
+    fn area_of(addr: &String) -> Option<String>{
+        (addr.len() > 1).then(|| addr[..1].to_string())
+    }
+});
+
+type AreaNetwork = Simulation<AreaSystem>;
+
+fn converged(nodes: &[&str], links: &[(i32, &str, &str, u16)]) -> AreaNetwork{
+    let mut network = create(nodes, links);
+    network.tick_n(15);
+    network
+}
+
+fn route_to(network: &AreaNetwork, cur: &str, dest: &str) -> Option<(String, u16)>{
+    let router = network.router(&cur.to_string()).unwrap();
+    router.route_to(&dest.to_string()).map(|route| (route.next_hop.clone(), route.metric))
+}
+
+fn destinations(network: &AreaNetwork, cur: &str) -> Vec<String>{
+    let mut dests: Vec<_> = network.router(&cur.to_string()).unwrap().routes.keys().cloned().collect();
+    dests.sort();
+    dests
+}
+
+#[test]
+fn interior_routes_stay_in_the_area(){
+    // a1 - a2 - b1 - b2
+    let network = converged(&["a1", "a2", "b1", "b2"], &[(0, "a1", "a2", 1), (1, "a2", "b1", 1), (2, "b1", "b2", 1)]);
+    assert_eq!(destinations(&network, "a1"), ["a2", "b"]);
+    assert_eq!(destinations(&network, "b2"), ["a", "b1"]);
+    assert_eq!(route_to(&network, "a1", "b2"), Some(("a2".to_string(), 2)));
+    assert_eq!(route_to(&network, "b2", "a1"), Some(("b1".to_string(), 2)));
+    assert_eq!(route_to(&network, "a1", "a2"), Some(("a2".to_string(), 1)));
+
+    let border: Vec<_> = ["a1", "a2", "b1", "b2"].iter()
+        .filter(|node| network.router(&node.to_string()).unwrap().is_border())
+        .collect();
+    assert_eq!(border, [&"a2", &"b1"]);
+    assert!(network.violations.is_empty());
+}
+
+#[test]
+fn summaries_cross_other_areas(){
+    // a1 - b1 - b2 - c1
+    let network = converged(&["a1", "b1", "b2", "c1"], &[(0, "a1", "b1", 1), (1, "b1", "b2", 1), (2, "b2", "c1", 1)]);
+    assert_eq!(destinations(&network, "c1"), ["a", "b"]);
+    assert_eq!(route_to(&network, "c1", "a1"), Some(("b2".to_string(), 3)));
+    assert_eq!(destinations(&network, "b1"), ["a", "b2", "c"]);
+}
+
+#[test]
+fn summary_fails_over_to_another_border(){
+    // b1 reaches area a through a1, or through a3 at a higher cost
+    let mut network = converged(
+        &["a1", "a2", "a3", "b1"],
+        &[(0, "a1", "a2", 1), (1, "a2", "a3", 1), (2, "b1", "a1", 1), (3, "b1", "a3", 5)]
+    );
+    assert_eq!(route_to(&network, "b1", "a2"), Some(("a1".to_string(), 1)));
+    network.set_link_metric(&2, INF);
+    network.tick_n(15);
+    assert_eq!(route_to(&network, "b1", "a2"), Some(("a3".to_string(), 5)));
+    assert!(!network.router(&"a1".to_string()).unwrap().is_border());
+    assert!(network.violations.is_empty());
+    assert!(network.errors.is_empty());
+}
+
+#[test]
+fn no_areas_by_default(){
+    let mut network: VirtualNetwork = create(&["a1", "b1"], &[(0, "a1", "b1", 1)]);
+    network.tick_n(5);
+    let router = network.router(&"a1".to_string()).unwrap();
+    assert!(!router.is_border());
+    assert_eq!(router.route_to(&"b1".to_string()).unwrap().metric, 1);
+}
@@ -0,0 +1,130 @@
+use std::collections::BTreeMap;
+use root::concepts::packet::{Capabilities, Packet};
+use root::sim::LinkFaults;
+use crate::common::{VirtualNetwork, VirtualSystem};
+
+mod common;
+
+fn attributes(pairs: &[(&str, &str)]) -> BTreeMap<String, String>{
+    pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+}
+
+fn set_attributes(network: &mut VirtualNetwork, node: &str, pairs: &[(&str, &str)]){
+    network.router_mut(&node.to_string()).unwrap().set_attributes(attributes(pairs));
+}
+
+fn attributes_of(network: &VirtualNetwork, cur: &str, node: &str) -> Option<BTreeMap<String, String>>{
+    network.router(&cur.to_string()).unwrap().attributes_of(&node.to_string()).cloned()
+}
+
+/// 1 - 2 - 3 - 4
+fn line() -> VirtualNetwork{
+    common::line(4)
+}
+
+#[test]
+fn attributes_reach_every_node(){
+    let mut network = line();
+    network.tick_n(10);
+    set_attributes(&mut network, "1", &[("hostname", "one"), ("role", "gateway")]);
+    network.tick_n(5);
+    for node in ["1", "2", "3", "4"]{
+        assert_eq!(attributes_of(&network, node, "1"), Some(attributes(&[("hostname", "one"), ("role", "gateway")])));
+    }
+    assert_eq!(attributes_of(&network, "1", "4"), None);
+    assert!(network.violations.is_empty());
+}
+
+#[test]
+fn changed_attributes_replace_old_ones(){
+    let mut network = line();
+    set_attributes(&mut network, "4", &[("version", "1")]);
+    network.tick_n(10);
+    let seqno = network.router(&"4".to_string()).unwrap().seqno;
+    set_attributes(&mut network, "4", &[("version", "2")]);
+    assert_ne!(network.router(&"4".to_string()).unwrap().seqno, seqno);
+    network.tick_n(5);
+    assert_eq!(attributes_of(&network, "1", "4"), Some(attributes(&[("version", "2")])));
+
+    // removing every attribute is announced too
+    set_attributes(&mut network, "4", &[]);
+    network.tick_n(5);
+    assert_eq!(attributes_of(&network, "1", "4"), Some(BTreeMap::new()));
+}
+
+#[test]
+fn lost_removal_is_announced_again(){
+    let mut network = line();
+    set_attributes(&mut network, "4", &[("version", "1")]);
+    network.tick_n(10);
+
+    // the announcement that 4 has no attributes anymore is lost
+    network.set_link_faults(&2, LinkFaults{ loss: 1.0, ..Default::default() });
+    set_attributes(&mut network, "4", &[]);
+    network.tick_n(2);
+    network.set_link_faults(&2, LinkFaults::default());
+    network.tick_n(5);
+    for node in ["1", "2", "3"]{
+        assert_eq!(attributes_of(&network, node, "4"), Some(BTreeMap::new()));
+    }
+    assert!(network.violations.is_empty());
+    assert!(network.errors.is_empty());
+}
+
+#[test]
+fn new_nodes_learn_attributes(){
+    let mut network = line();
+    set_attributes(&mut network, "1", &[("hostname", "one")]);
+    network.tick_n(10);
+    network.add_router("5".to_string());
+    network.add_link(3, "4".to_string(), "5".to_string(), 1);
+    network.tick_n(5);
+    assert_eq!(attributes_of(&network, "5", "1"), Some(attributes(&[("hostname", "one")])));
+}
+
+#[test]
+fn attributes_of_unreachable_nodes_expire(){
+    let mut network = line();
+    set_attributes(&mut network, "4", &[("hostname", "four")]);
+    network.tick_n(10);
+    assert!(attributes_of(&network, "1", "4").is_some());
+    network.remove_link(&2);
+    network.tick_n(10);
+    for node in ["1", "2", "3"]{
+        assert_eq!(attributes_of(&network, node, "4"), None);
+    }
+    assert!(attributes_of(&network, "1", "3").is_none());
+}
+
+#[test]
+fn updates_only_forward_changed_attributes(){
+    // 2 learns the attributes of 1 and 3, and forwards them
+    let mut one = common::router::<VirtualSystem>("1", &[(0, "2")]);
+    let mut two = common::router::<VirtualSystem>("2", &[(0, "1"), (1, "3")]);
+    let mut three = common::router::<VirtualSystem>("3", &[(1, "2")]);
+    for router in [&mut one, &mut two, &mut three]{
+        common::assume_negotiated(router, Capabilities::ATTRIBUTES);
+    }
+    one.set_attributes(attributes(&[("hostname", "one")]));
+    three.set_attributes(attributes(&[("hostname", "three")]));
+    one.update();
+    three.update();
+    common::deliver(&mut two, "1", one.take_outbound_packets());
+    common::deliver(&mut two, "3", three.take_outbound_packets());
+    two.update();
+    two.take_outbound_packets();
+
+    // only the attributes of 3 change, so an update does not send the ones of 1 again
+    three.set_attributes(attributes(&[("hostname", "drei")]));
+    three.update();
+    common::deliver(&mut two, "3", three.take_outbound_packets());
+    two.update();
+    let forwarded: Vec<_> = two.take_outbound_packets().into_iter()
+        .filter(|packet| packet.dest == "1")
+        .flat_map(|packet| match packet.packet.data{
+            Packet::AttributeUpdate{ attributes } => attributes.iter().map(|node| node.data.source.addr.clone()).collect(),
+            _ => Vec::new(),
+        })
+        .collect();
+    assert_eq!(forwarded, ["3"]);
+}
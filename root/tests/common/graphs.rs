@@ -1,7 +1,7 @@
-use crate::common::virtual_network::VirtualSystem;
+use crate::common::{create, VirtualNetwork};
 
-pub fn vnet_simple_weighted() -> VirtualSystem{
-    VirtualSystem::create(
+pub fn vnet_simple_weighted() -> VirtualNetwork{
+    create(
         &["1", "2", "3", "4", "5"],
         &[
             (0, "1", "2", 2),
@@ -15,8 +15,8 @@ pub fn vnet_simple_weighted() -> VirtualSystem{
     )
 }
 
-pub fn vnet_fragile_network() -> VirtualSystem{
-    VirtualSystem::create(
+pub fn vnet_fragile_network() -> VirtualNetwork{
+    create(
         &["1", "2", "3", "4", "5"],
         &[
             (0, "1", "2", 1),
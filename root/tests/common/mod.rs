@@ -1,3 +1,162 @@
+// each integration test only uses part of the shared harness
+#![allow(dead_code)]
+use root::concepts::neighbour::{Neighbour, NeighbourProtocol};
+use root::concepts::packet::{Capabilities, OutboundPacket, PROTOCOL_VERSION};
+use root::framework::{MACSignature, RoutingSystem};
+use root::router::{Router, INF};
+use root::sim::invariants::InvariantChecker;
+use root::sim::Simulation;
+
 #[cfg(test)]
 pub mod graphs;
-mod virtual_network;
\ No newline at end of file
+
+/// Declares a routing system like the ones the test networks use, with string addresses and numbered links.
+/// Only the consts and functions given in the braces are overridden, the `MACSystem`, `RouteSelector` and `Extension`
+/// types default to `NoMACSystem`, `LowestMetric` and `()`, unless they are given there too:
+///
+/// ```ignore
+/// common::test_system!(AckSystem {
+///     const REQUEST_ACKS: bool = true;
+/// });
+/// ```
+macro_rules! test_system {
+    (@munch [$vis:vis] $name:ident [$mac:ty] [$selector:ty] [$extension:ty] [$($items:tt)*] type MACSystem = $ty:ty; $($rest:tt)*) => {
+        $crate::common::test_system!(@munch [$vis] $name [$ty] [$selector] [$extension] [$($items)*] $($rest)*);
+    };
+    (@munch [$vis:vis] $name:ident [$mac:ty] [$selector:ty] [$extension:ty] [$($items:tt)*] type RouteSelector = $ty:ty; $($rest:tt)*) => {
+        $crate::common::test_system!(@munch [$vis] $name [$mac] [$ty] [$extension] [$($items)*] $($rest)*);
+    };
+    (@munch [$vis:vis] $name:ident [$mac:ty] [$selector:ty] [$extension:ty] [$($items:tt)*] type Extension = $ty:ty; $($rest:tt)*) => {
+        $crate::common::test_system!(@munch [$vis] $name [$mac] [$selector] [$ty] [$($items)*] $($rest)*);
+    };
+    (@munch [$vis:vis] $name:ident [$mac:ty] [$selector:ty] [$extension:ty] [$($items:tt)*] $next:tt $($rest:tt)*) => {
+        $crate::common::test_system!(@munch [$vis] $name [$mac] [$selector] [$extension] [$($items)* $next] $($rest)*);
+    };
+    (@munch [$vis:vis] $name:ident [$mac:ty] [$selector:ty] [$extension:ty] [$($items:tt)*]) => {
+        $vis struct $name {}
+
+        impl root::framework::RoutingSystem for $name{
+            type NodeAddress = String;
+            type Link = i32;
+            type MACSystem = $mac;
+            type RouteSelector = $selector;
+            type Extension = $extension;
+            $($items)*
+        }
+    };
+    ($vis:vis $name:ident { $($body:tt)* }) => {
+        $crate::common::test_system!(@munch [$vis] $name [root::router::NoMACSystem] [root::router::LowestMetric] [()] [] $($body)*);
+    };
+}
+// not every integration test declares its own system
+#[allow(unused_imports)]
+pub(crate) use test_system;
+
+test_system!(pub VirtualSystem {});
+
+pub type VirtualNetwork = Simulation<VirtualSystem>;
+
+/// a routing system whose nodes are named by strings, and whose links are numbered, like the ones the test networks use
+pub trait TestSystem: RoutingSystem<NodeAddress = String, Link = i32> {}
+impl<T: RoutingSystem<NodeAddress = String, Link = i32>> TestSystem for T {}
+
+pub fn create<T: TestSystem>(nodes: &[&str], links: &[(i32, &str, &str, u16)]) -> Simulation<T>{
+    let mut network = Simulation::new();
+    for node in nodes{
+        network.add_router(node.to_string());
+    }
+    for (link, a, b, metric) in links{
+        network.add_link(*link, a.to_string(), b.to_string(), *metric);
+    }
+    network
+}
+
+/// 1 - 2 - ... - len, where link i connects i + 1 and i + 2 with metric 1
+pub fn line<T: TestSystem>(len: usize) -> Simulation<T>{
+    let names: Vec<String> = (1..=len).map(|node| node.to_string()).collect();
+    let nodes: Vec<&str> = names.iter().map(String::as_str).collect();
+    let links: Vec<_> = nodes.windows(2).zip(0..).map(|(pair, link)| (link, pair[0], pair[1], 1)).collect();
+    create(&nodes, &links)
+}
+
+/// ticks the network, checking the network-wide invariants after every tick
+pub fn tick_checked<T: TestSystem>(network: &mut Simulation<T>, checker: &mut InvariantChecker<T>, times: usize){
+    for _ in 0..times{
+        network.tick();
+        let violations = checker.check(&network.routers);
+        assert!(violations.is_empty(), "tick {}: {:?}", network.time, violations);
+        assert!(network.violations.is_empty(), "tick {}: {:?}", network.time, network.violations);
+    }
+}
+
+/// shorthands for inspecting the test networks, inspecting a network asserts that no router returned an error so far
+pub trait NetworkExt{
+    fn update_edge(&mut self, edge_id: i32, metric: u16);
+    fn get_next_hop(&self, cur: &str, src: &str) -> String;
+    fn get_metric_to(&self, cur: &str, src: &str) -> u16;
+    fn get_seqno_to(&self, cur: &str, src: &str) -> u16;
+    /// the metric of the route, or INF if there is none
+    fn metric_or_inf(&self, cur: &str, dest: &str) -> u16;
+    /// the next hop and metric of the route, if it is reachable
+    fn reachable_route(&self, cur: &str, dest: &str) -> Option<(String, u16)>;
+}
+
+impl<T: TestSystem> NetworkExt for Simulation<T>{
+    fn update_edge(&mut self, edge_id: i32, metric: u16){
+        self.set_link_metric(&edge_id, metric);
+    }
+
+    fn get_next_hop(&self, cur: &str, src: &str) -> String{
+        assert_no_errors(self);
+        self.route(&cur.to_string(), &src.to_string()).unwrap_or_else(|| panic!("No route found to {src} from {cur}")).next_hop.clone()
+    }
+
+    fn get_metric_to(&self, cur: &str, src: &str) -> u16{
+        assert_no_errors(self);
+        self.route(&cur.to_string(), &src.to_string()).unwrap_or_else(|| panic!("No route found to {src} from {cur}")).metric
+    }
+
+    fn get_seqno_to(&self, cur: &str, src: &str) -> u16{
+        assert_no_errors(self);
+        self.route(&cur.to_string(), &src.to_string()).unwrap_or_else(|| panic!("No route found to {src} from {cur}")).source.data().seqno
+    }
+
+    fn metric_or_inf(&self, cur: &str, dest: &str) -> u16{
+        assert_no_errors(self);
+        self.route(&cur.to_string(), &dest.to_string()).map_or(INF, |route| route.metric)
+    }
+
+    fn reachable_route(&self, cur: &str, dest: &str) -> Option<(String, u16)>{
+        assert_no_errors(self);
+        self.route(&cur.to_string(), &dest.to_string())
+            .filter(|route| route.metric != INF)
+            .map(|route| (route.next_hop.clone(), route.metric))
+    }
+}
+
+fn assert_no_errors<T: TestSystem>(network: &Simulation<T>){
+    assert!(network.errors.is_empty(), "{:?}", network.errors);
+}
+
+/// a router that is not part of a simulation, with a neighbour on each of the links
+pub fn router<T: TestSystem>(addr: &str, neighs: &[(i32, &str)]) -> Router<T>{
+    let mut router = Router::new(addr.to_string());
+    for (link, neigh) in neighs{
+        router.links.insert(*link, Neighbour::new(neigh.to_string()));
+    }
+    router
+}
+
+/// marks every neighbour of the router as running this release with the given capabilities, as if they had exchanged hellos
+pub fn assume_negotiated<T: TestSystem>(router: &mut Router<T>, capabilities: Capabilities){
+    for neighbour in router.links.values_mut(){
+        neighbour.protocol = NeighbourProtocol::Known{ version: PROTOCOL_VERSION, capabilities };
+    }
+}
+
+/// hands the packets that another router sent to a router that is not part of a simulation
+pub fn deliver<T: TestSystem>(router: &mut Router<T>, from: &str, packets: Vec<OutboundPacket<T>>){
+    for packet in packets{
+        router.handle_packet(&packet.packet, &packet.link, &from.to_string()).unwrap();
+    }
+}
@@ -0,0 +1,56 @@
+use root::router::INF;
+use root::sim::LinkFaults;
+use crate::common::VirtualNetwork;
+
+mod common;
+use common::NetworkExt;
+
+#[test]
+fn stable_packet_order(){
+    let mut a = common::graphs::vnet_simple_weighted();
+    let mut b = common::graphs::vnet_simple_weighted();
+    for _ in 0..10 {
+        a.tick();
+        b.tick();
+        // the in-flight packets of both networks must be identical, including their order
+        assert_eq!(a.freeze(), b.freeze());
+    }
+}
+
+#[test]
+fn equal_metric_tie_break(){
+    // 1 has two equal-cost paths to 4, via 2 (link 0) and via 3 (link 1)
+    let mut network: VirtualNetwork = common::create(
+        &["1", "2", "3", "4"],
+        &[
+            (1, "1", "3", 1),
+            (0, "1", "2", 1),
+            (2, "2", "4", 1),
+            (3, "3", "4", 1),
+        ]
+    );
+    network.tick_n(10);
+    assert_eq!(network.get_metric_to("1", "4"), 2);
+    assert_eq!(network.get_next_hop("1", "4"), "2"); // the smallest link wins
+}
+
+#[test]
+fn seeded_faults_are_reproducible(){
+    let faults = LinkFaults{
+        loss: 0.3,
+        latency: 2,
+        duplication: 0.2,
+        reordering: 0.3,
+    };
+    let mut a = common::graphs::vnet_simple_weighted();
+    let mut b = common::graphs::vnet_simple_weighted();
+    for network in [&mut a, &mut b]{
+        network.set_seed(7).set_all_link_faults(faults.clone());
+        network.update_edge(3, INF);
+    }
+    for _ in 0..20 {
+        a.tick();
+        b.tick();
+        assert_eq!(a.freeze(), b.freeze());
+    }
+}
@@ -1,6 +1,10 @@
-use root::router::INF;
+use root::concepts::packet::{Packet, RouteUpdate};
+use root::concepts::route::Source;
+use root::router::{DummyMAC, INF};
+use crate::common::VirtualNetwork;
 
 mod common;
+use common::NetworkExt;
 
 #[test]
 fn retraction_link_down(){
@@ -40,4 +44,95 @@ fn link_recovery_inf_sum(){
     network.update_edge(3, INF-1);
     network.tick_n(6);
     assert_eq!(network.get_metric_to("3", "5"), INF-1); // should never be INF, since that is retracted
-}
\ No newline at end of file
+}
+
+#[test]
+fn unreachable_route_with_newer_seqno_is_not_selected(){
+    // 1 - 2 - 3
+    let mut network: VirtualNetwork = common::create(&["1", "2", "3"], &[(0, "1", "2", 1), (1, "2", "3", 1)]);
+    network.tick_n(10);
+
+    // 1 missed the seqno update of 3, and only hears its retraction
+    let retraction = DummyMAC::from(Packet::UrgentRouteUpdate(RouteUpdate{
+        source: DummyMAC::from(Source{ addr: "3".to_string(), seqno: 1, injected: false }),
+        metric: INF,
+        hop_count: 1,
+    }));
+    let router = network.router_mut(&"1".to_string()).unwrap();
+    router.handle_packet(&retraction, &0, &"2".to_string()).unwrap();
+    router.full_update();
+    // the route is retracted, rather than replaced by the unreachable one
+    let route = router.routes.get("3").unwrap();
+    assert_eq!(route.metric, INF);
+    assert_eq!(route.fd, 2);
+    assert!(route.retracted);
+
+    network.tick_n(5);
+    assert_eq!(network.get_metric_to("1", "3"), 2);
+    assert_eq!(network.get_seqno_to("1", "3"), 1);
+    assert!(network.errors.is_empty());
+}
+
+/// follows the next hops of every route in use, and panics if a packet would come back to a router it has visited
+fn assert_loop_free(network: &VirtualNetwork){
+    for router in &network.routers{
+        for dest in router.routes.keys(){
+            let mut path = vec![router.address.clone()];
+            let mut cur = router.address.clone();
+            while let Some(route) = network.route(&cur, dest).filter(|route| route.metric != INF && cur != *dest){
+                cur = route.next_hop.clone();
+                assert!(!path.contains(&cur), "forwarding loop to {dest}: {path:?} -> {cur}");
+                path.push(cur.clone());
+            }
+        }
+    }
+}
+
+#[test]
+fn route_can_be_retracted_again(){
+    // 1 - 2 - 3
+    let mut network: VirtualNetwork = common::create(&["1", "2", "3"], &[(0, "1", "2", 1), (1, "2", "3", 1)]);
+    network.tick_n(10);
+    for _ in 0..2{
+        network.update_edge(1, INF);
+        network.tick_n(5);
+        assert_eq!(network.get_metric_to("1", "3"), INF);
+
+        network.update_edge(1, 1);
+        network.tick_n(5);
+        assert_eq!(network.get_metric_to("1", "3"), 2);
+    }
+}
+
+#[test]
+fn saturated_metric_does_not_loop(){
+    let mut network = common::graphs::vnet_fragile_network();
+    network.tick_n(10);
+    // the metrics through 1 - 4 saturate at INF - 1, so a route that loops back through 1 has the same metric as its fd
+    network.update_edge(3, INF - 1);
+    for _ in 0..6{
+        network.tick();
+        assert_loop_free(&network);
+    }
+    network.update_edge(3, INF);
+    for _ in 0..10{
+        network.tick();
+        assert_loop_free(&network);
+    }
+}
+
+#[test]
+fn parallel_links_converge(){
+    // 1 = 2 - 3, with two links between 1 and 2
+    let mut network: VirtualNetwork = common::create(&["1", "2", "3"], &[(0, "1", "2", 1), (1, "1", "2", 5), (2, "2", "3", 1)]);
+    network.tick_n(10);
+    // the advert over the worse link is not a metric increase of the selected route
+    assert_eq!(network.get_metric_to("1", "3"), 2);
+    assert_eq!(network.route(&"1".to_string(), &"3".to_string()).unwrap().link, 0);
+
+    network.update_edge(0, 10);
+    network.tick_n(10);
+    assert_eq!(network.get_metric_to("1", "3"), 6);
+    assert_eq!(network.route(&"1".to_string(), &"3".to_string()).unwrap().link, 1);
+    assert!(network.errors.is_empty());
+}
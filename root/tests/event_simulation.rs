@@ -0,0 +1,64 @@
+use root::router::INF;
+use root::sim::event::EventSimulation;
+use crate::common::VirtualSystem;
+
+mod common;
+
+const UPDATE_INTERVAL: u64 = 1000;
+const QUIET: u64 = 5 * UPDATE_INTERVAL;
+const LIMIT: u64 = 60_000;
+
+/// the simple weighted network, where every link has 10ms of latency
+fn simple_weighted(bandwidth: u64) -> EventSimulation<VirtualSystem>{
+    let mut network = EventSimulation::new(UPDATE_INTERVAL);
+    for (i, node) in ["1", "2", "3", "4", "5"].iter().enumerate(){
+        network.add_router(node.to_string(), i as u64 * 170);
+    }
+    for (link, a, b, metric) in [(0, "1", "2", 2), (1, "1", "3", 1), (2, "2", "3", 4), (3, "2", "4", 5), (4, "3", "4", 100), (5, "3", "5", 8), (6, "4", "5", 1)]{
+        network.add_link(link, a.to_string(), b.to_string(), metric, 10, bandwidth);
+    }
+    network
+}
+
+fn metric(network: &EventSimulation<VirtualSystem>, cur: &str, dest: &str) -> u16{
+    network.route(&cur.to_string(), &dest.to_string()).unwrap().metric
+}
+
+fn next_hop(network: &EventSimulation<VirtualSystem>, cur: &str, dest: &str) -> String{
+    network.route(&cur.to_string(), &dest.to_string()).unwrap().next_hop.clone()
+}
+
+#[test]
+fn converges_in_simulated_time(){
+    let mut network = simple_weighted(0);
+    let time = network.run_until_converged(QUIET, LIMIT).expect("network did not converge");
+    assert!(time > 0);
+    assert!(time < 10 * UPDATE_INTERVAL, "took {time}ms");
+    assert_eq!(metric(&network, "1", "5"), 8);
+    assert_eq!(next_hop(&network, "1", "5"), "2");
+    assert!(network.errors.is_empty());
+    assert!(network.violations.is_empty(), "{:?}", network.violations);
+}
+
+#[test]
+fn bandwidth_slows_convergence(){
+    let mut fast = simple_weighted(0);
+    let mut slow = simple_weighted(1000);
+    let fast_time = fast.run_until_converged(QUIET, LIMIT).unwrap();
+    let slow_time = slow.run_until_converged(QUIET, LIMIT).unwrap();
+    assert!(slow_time > fast_time, "slow: {slow_time}ms, fast: {fast_time}ms");
+    assert_eq!(metric(&slow, "1", "5"), 8);
+}
+
+#[test]
+fn retraction_converges(){
+    let mut network = simple_weighted(0);
+    network.run_until_converged(QUIET, LIMIT).unwrap();
+
+    network.set_link_metric(3, INF, 0);
+    let time = network.run_until_converged(QUIET, LIMIT).expect("network did not converge");
+    assert_eq!(metric(&network, "1", "5"), 9);
+    assert_eq!(next_hop(&network, "1", "5"), "3");
+    // the retraction is urgent, but the starved route has to wait for a seqno update
+    assert!(time <= 3 * UPDATE_INTERVAL, "took {time}ms");
+}
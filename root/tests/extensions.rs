@@ -0,0 +1,52 @@
+use root::concepts::packet::{Capabilities, Packet, PacketPriority};
+use root::router::Router;
+use crate::common::{assume_negotiated, deliver};
+
+mod common;
+
+common::test_system!(ExtensionSystem {
+    type Extension = String;
+    const MAX_EXTENSION_LENGTH: usize = 2;
+});
+
+fn router(addr: &str, neighs: &[(i32, &str)], capabilities: Capabilities) -> Router<ExtensionSystem>{
+    let mut router = common::router(addr, neighs);
+    assume_negotiated(&mut router, capabilities);
+    router
+}
+
+#[test]
+fn extensions_are_delivered(){
+    let mut a = router("1", &[(0, "2"), (1, "3")], Capabilities::ALL);
+    let mut b = router("2", &[(0, "1")], Capabilities::ALL);
+    a.write_extension(&0, "hi 2".to_string());
+    a.broadcast_extension("hi all".to_string());
+    let packets: Vec<_> = a.take_outbound_packets().into_iter().filter(|x| x.dest == "2").collect();
+    assert!(packets.iter().all(|x| x.priority() == PacketPriority::Periodic));
+    deliver(&mut b, "1", packets);
+    let received: Vec<_> = b.extensions.drain(..).map(|x| (x.link, x.neighbour, x.data)).collect();
+    assert_eq!(received, [
+        (0, "1".to_string(), "hi 2".to_string()),
+        (0, "1".to_string(), "hi all".to_string()),
+    ]);
+}
+
+#[test]
+fn extensions_are_not_sent_to_old_neighbours(){
+    let mut a = router("1", &[(0, "2")], Capabilities(Capabilities::ALL.0 & !Capabilities::EXTENSIONS.0));
+    a.write_extension(&0, "hi".to_string());
+    a.write_extension(&1, "nobody".to_string());
+    assert!(a.take_outbound_packets().iter().all(|x| !matches!(x.packet.data, Packet::Extension { .. })));
+}
+
+#[test]
+fn oldest_extensions_are_dropped(){
+    let mut a = router("1", &[(0, "2")], Capabilities::ALL);
+    let mut b = router("2", &[(0, "1")], Capabilities::ALL);
+    for data in ["1", "2", "3"]{
+        a.write_extension(&0, data.to_string());
+    }
+    deliver(&mut b, "1", a.take_outbound_packets());
+    let received: Vec<_> = b.extensions.iter().map(|x| x.data.as_str()).collect();
+    assert_eq!(received, ["2", "3"]);
+}
@@ -0,0 +1,130 @@
+use root::concepts::packet::Packet;
+use root::feedback::RoutingWarning;
+use root::router::INF;
+use root::sim::Simulation;
+use crate::common::{create, TestSystem, VirtualNetwork};
+
+mod common;
+use common::NetworkExt;
+
+common::test_system!(DampedSystem {
+    const FLAP_PENALTY: u32 = 1000;
+    const FLAP_SUPPRESS_THRESHOLD: u32 = 2000;
+    const FLAP_REUSE_THRESHOLD: u32 = 750;
+    const FLAP_HALF_LIFE_ROUNDS: u32 = 4;
+});
+
+type DampedNetwork = Simulation<DampedSystem>;
+
+fn suppressed<T: TestSystem>(network: &Simulation<T>, cur: &str, dest: &str) -> bool{
+    network.router(&cur.to_string()).unwrap().is_suppressed(&dest.to_string())
+}
+
+/// 1 - 2 - 3, where 3 is behind an unstable link
+fn network<T: TestSystem>() -> Simulation<T>{
+    let mut network = create(&["1", "2", "3"], &[(0, "1", "2", 1), (1, "2", "3", 1)]);
+    network.tick_n(10);
+    assert_eq!(network.metric_or_inf("1", "3"), 2);
+    network
+}
+
+fn flap<T: TestSystem>(network: &mut Simulation<T>){
+    network.set_link_metric(&1, INF);
+    network.tick();
+    network.set_link_metric(&1, 1);
+    network.tick();
+}
+
+#[test]
+fn single_flap_is_not_suppressed(){
+    let mut network: DampedNetwork = network();
+    flap(&mut network);
+    network.tick_n(3);
+    assert!(!suppressed(&network, "2", "3"));
+    assert_eq!(network.metric_or_inf("1", "3"), 2);
+}
+
+#[test]
+fn flapping_route_is_suppressed(){
+    let mut network: DampedNetwork = network();
+    for _ in 0..3{
+        flap(&mut network);
+    }
+    assert!(suppressed(&network, "2", "3"));
+    let warnings: Vec<_> = network.router_mut(&"2".to_string()).unwrap().warnings.drain(..).collect();
+    assert_eq!(
+        warnings.iter().filter(|w| matches!(w, RoutingWarning::RouteSuppressed{addr} if addr == "3")).count(),
+        1
+    );
+
+    // 2 still routes to 3 itself, but no longer advertises it
+    network.tick();
+    assert_eq!(network.metric_or_inf("2", "3"), 1);
+    assert_eq!(network.metric_or_inf("1", "3"), INF);
+    // other destinations are not affected
+    assert_eq!(network.metric_or_inf("1", "2"), 1);
+    assert!(network.violations.is_empty());
+}
+
+#[test]
+fn suppressed_route_is_advertised_after_decay(){
+    let mut network: DampedNetwork = network();
+    for _ in 0..3{
+        flap(&mut network);
+    }
+    assert!(suppressed(&network, "2", "3"));
+
+    network.tick_n(20);
+    assert!(!suppressed(&network, "2", "3"));
+    assert_eq!(network.metric_or_inf("1", "3"), 2);
+}
+
+#[test]
+fn suppression_survives_freeze_restore(){
+    let mut network: DampedNetwork = network();
+    for _ in 0..3{
+        flap(&mut network);
+    }
+    let mut restored = DampedNetwork::restore(&network.freeze()).unwrap();
+    assert!(suppressed(&restored, "2", "3"));
+
+    // the penalty keeps decaying from where it was
+    for _ in 0..20{
+        network.tick();
+        restored.tick();
+        assert_eq!(suppressed(&restored, "2", "3"), suppressed(&network, "2", "3"));
+        assert_eq!(restored.metric_or_inf("1", "3"), network.metric_or_inf("1", "3"));
+    }
+    assert!(!suppressed(&restored, "2", "3"));
+}
+
+#[test]
+fn flaps_while_suppressed_are_not_propagated(){
+    let mut network: DampedNetwork = network();
+    for _ in 0..3{
+        flap(&mut network);
+    }
+    network.tick();
+
+    // 2 already advertises 3 as unreachable, so it does not send a retraction for every flap
+    network.set_link_metric(&1, INF);
+    let router = network.router_mut(&"2".to_string()).unwrap();
+    router.take_outbound_packets();
+    router.update();
+    assert!(router.routes[&"3".to_string()].retracted);
+    assert!(router.outbound_packets().iter().all(|packet| !matches!(
+        &packet.packet.data,
+        Packet::UrgentRouteUpdate(update) if update.source.data.addr == "3"
+    )));
+}
+
+#[test]
+fn damping_is_disabled_by_default(){
+    let mut network: VirtualNetwork = network();
+    for _ in 0..10{
+        flap(&mut network);
+    }
+    network.tick_n(3);
+    assert!(!suppressed(&network, "2", "3"));
+    assert_eq!(network.metric_or_inf("1", "3"), 2);
+}
@@ -0,0 +1,67 @@
+use root::concepts::packet::RouteUpdate;
+use root::router::INF;
+use root::sim::Simulation;
+use crate::common::{create, VirtualNetwork, VirtualSystem};
+
+mod common;
+use common::NetworkExt;
+
+common::test_system!(SmallSystem {
+    const MAX_DIAMETER: u8 = 3;
+});
+
+type SmallNetwork = Simulation<SmallSystem>;
+
+/// 1 - 2 - 3 - 4 - 5
+fn line() -> SmallNetwork{
+    common::line(5)
+}
+
+#[test]
+fn hop_count_is_tracked(){
+    let mut network: VirtualNetwork = create(
+        &["1", "2", "3", "4"],
+        &[(0, "1", "2", 1), (1, "2", "3", 1), (2, "3", "4", 1), (3, "1", "4", 10)]
+    );
+    network.tick_n(10);
+    let hops = |cur: &str, dest: &str| network.route(&cur.to_string(), &dest.to_string()).unwrap().hop_count;
+    assert_eq!(hops("1", "2"), 1);
+    assert_eq!(hops("1", "3"), 2);
+    // the cheaper route is longer
+    assert_eq!(hops("1", "4"), 3);
+    assert_eq!(hops("4", "1"), 3);
+}
+
+#[test]
+fn routes_beyond_diameter_are_unreachable(){
+    let mut network = line();
+    network.tick_n(10);
+    assert_eq!(network.route(&"1".to_string(), &"4".to_string()).unwrap().hop_count, 3);
+    assert_eq!(network.metric_or_inf("1", "4"), 3);
+    assert_eq!(network.metric_or_inf("1", "5"), INF);
+    assert_eq!(network.metric_or_inf("5", "1"), INF);
+    assert_eq!(network.metric_or_inf("5", "2"), 3);
+    assert!(network.violations.is_empty());
+}
+
+#[test]
+fn shorter_route_is_used_within_diameter(){
+    // 1 - 5 is expensive, but the only route within the diameter
+    let mut network = line();
+    network.add_link(4, "1".to_string(), "5".to_string(), 20);
+    network.tick_n(10);
+    let route = network.route(&"1".to_string(), &"5".to_string()).unwrap();
+    assert_eq!(route.next_hop, "5");
+    assert_eq!(route.metric, 20);
+    assert_eq!(route.hop_count, 1);
+    assert_eq!(network.metric_or_inf("2", "5"), 3);
+}
+
+#[test]
+fn missing_hop_count_defaults_to_zero(){
+    let update: RouteUpdate<VirtualSystem> = serde_json::from_str(
+        r#"{"source": {"data": {"addr": "1", "seqno": 3}}, "metric": 5}"#
+    ).unwrap();
+    assert_eq!(update.metric, 5);
+    assert_eq!(update.hop_count, 0);
+}
@@ -0,0 +1,92 @@
+use root::router::INF;
+use root::sim::Simulation;
+use crate::common::{create, TestSystem, VirtualNetwork};
+
+mod common;
+use common::NetworkExt;
+
+common::test_system!(SmoothSystem {
+    const SWITCH_THRESHOLD: u16 = 2;
+    const SWITCH_HOLD_ROUNDS: u64 = 3;
+});
+
+type SmoothNetwork = Simulation<SmoothSystem>;
+
+/// 1 - 3 with metric 10, and 1 - 2 - 3 which starts out down
+fn network<T: TestSystem>(via_2: u16) -> Simulation<T>{
+    let mut network = create(
+        &["1", "2", "3"],
+        &[(0, "1", "3", 10), (1, "1", "2", INF), (2, "2", "3", via_2)]
+    );
+    network.tick_n(10);
+    assert_eq!(network.get_next_hop("1", "3"), "3");
+    network
+}
+
+#[test]
+fn small_improvements_are_ignored(){
+    // 1 - 2 - 3 costs 8, which is not better than 10 by more than the threshold
+    let mut network: SmoothNetwork = network(7);
+    network.set_link_metric(&1, 1);
+    network.tick_n(20);
+    assert_eq!(network.get_next_hop("1", "3"), "3");
+    assert_eq!(network.get_metric_to("1", "3"), 10);
+}
+
+#[test]
+fn switches_after_hold_time(){
+    // 1 - 2 - 3 costs 2
+    let mut network: SmoothNetwork = network(1);
+    network.set_link_metric(&1, 1);
+    // 1 already knows the route over 2, which has to stay better for 3 full updates after the first one that sees it
+    network.tick_n(3);
+    assert_eq!(network.get_next_hop("1", "3"), "3");
+    network.tick();
+    assert_eq!(network.get_next_hop("1", "3"), "2");
+    assert_eq!(network.get_metric_to("1", "3"), 2);
+}
+
+#[test]
+fn hold_time_survives_freeze_restore(){
+    let mut network: SmoothNetwork = network(1);
+    network.set_link_metric(&1, 1);
+    network.tick_n(3);
+    // the route over 2 is still held back, and is selected in the same round as without the restore
+    let mut restored = SmoothNetwork::restore(&network.freeze()).unwrap();
+    assert_eq!(restored.get_next_hop("1", "3"), "3");
+    restored.tick();
+    assert_eq!(restored.get_next_hop("1", "3"), "2");
+}
+
+#[test]
+fn flapping_route_is_not_selected(){
+    let mut network: SmoothNetwork = network(1);
+    for _ in 0..10{
+        network.set_link_metric(&1, 1);
+        network.tick_n(3);
+        network.set_link_metric(&1, 20);
+        network.tick_n(3);
+        assert_eq!(network.get_next_hop("1", "3"), "3");
+    }
+}
+
+#[test]
+fn unreachable_route_switches_immediately(){
+    let mut network: SmoothNetwork = network(7);
+    network.set_link_metric(&1, 1);
+    network.tick_n(10);
+    assert_eq!(network.get_next_hop("1", "3"), "3");
+
+    // the worse route is taken as soon as the selected one breaks
+    network.set_link_metric(&0, INF);
+    network.tick_n(3);
+    assert_eq!(network.get_next_hop("1", "3"), "2");
+}
+
+#[test]
+fn default_switches_immediately(){
+    let mut network: VirtualNetwork = network(7);
+    network.set_link_metric(&1, 1);
+    network.tick_n(3);
+    assert_eq!(network.get_next_hop("1", "3"), "2");
+}
@@ -0,0 +1,60 @@
+use std::collections::BTreeMap;
+use root::router::INF;
+use crate::common::VirtualNetwork;
+
+mod common;
+use common::NetworkExt;
+
+/// (node, source) -> (metric, fd, seqno, next hop, retracted)
+type Tables = BTreeMap<(String, String), (u16, u16, u16, String, bool)>;
+
+fn tables(network: &VirtualNetwork) -> Tables {
+    let mut tables = BTreeMap::new();
+    for router in &network.routers {
+        for (src, route) in &router.routes {
+            tables.insert(
+                (router.address.clone(), src.clone()),
+                (route.metric, route.fd, route.source.data.seqno, route.next_hop.clone(), route.retracted)
+            );
+        }
+    }
+    tables
+}
+
+/// ticks both networks, forcing the reference network to recompute every source
+fn tick_both(incremental: &mut VirtualNetwork, reference: &mut VirtualNetwork, times: i32) {
+    for _ in 0..times {
+        for router in &mut reference.routers {
+            router.invalidate_routes();
+        }
+        incremental.tick();
+        reference.tick();
+        assert_eq!(tables(incremental), tables(reference));
+    }
+}
+
+#[test]
+fn incremental_matches_full_recompute(){
+    let mut incremental = common::graphs::vnet_simple_weighted();
+    let mut reference = common::graphs::vnet_simple_weighted();
+    tick_both(&mut incremental, &mut reference, 10);
+
+    for (edge, metric) in [(3, INF), (5, 1), (4, 1), (4, 2), (3, 5), (6, INF), (6, 1)] {
+        incremental.update_edge(edge, metric);
+        reference.update_edge(edge, metric);
+        tick_both(&mut incremental, &mut reference, 6);
+    }
+}
+
+#[test]
+fn incremental_matches_full_recompute_fragile(){
+    let mut incremental = common::graphs::vnet_fragile_network();
+    let mut reference = common::graphs::vnet_fragile_network();
+    tick_both(&mut incremental, &mut reference, 10);
+
+    for (edge, metric) in [(3, 11), (3, INF - 1), (0, INF), (0, 1), (3, 10)] {
+        incremental.update_edge(edge, metric);
+        reference.update_edge(edge, metric);
+        tick_both(&mut incremental, &mut reference, 6);
+    }
+}
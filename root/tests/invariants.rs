@@ -0,0 +1,121 @@
+use root::feedback::ConsistencyViolation;
+use root::router::INF;
+use root::sim::invariants::{InvariantChecker, Violation};
+use root::sim::LinkFaults;
+use crate::common::tick_checked;
+
+mod common;
+
+#[test]
+fn invariants_hold_through_link_changes(){
+    for mut network in [common::graphs::vnet_simple_weighted(), common::graphs::vnet_fragile_network()]{
+        let mut checker = InvariantChecker::new();
+        tick_checked(&mut network, &mut checker, 10);
+        for (edge, metric) in [(3, INF), (5, 1), (4, 1), (4, 2), (3, 11), (3, INF - 1), (6, INF), (6, 1), (0, INF), (0, 1)]{
+            network.set_link_metric(&edge, metric);
+            tick_checked(&mut network, &mut checker, 6);
+        }
+    }
+}
+
+#[test]
+fn invariants_hold_under_loss(){
+    for seed in 0..20{
+        let mut network = common::graphs::vnet_simple_weighted();
+        let mut checker = InvariantChecker::new();
+        network.set_seed(seed).set_all_link_faults(LinkFaults{
+            loss: 0.3,
+            latency: 2,
+            duplication: 0.2,
+            reordering: 0.3,
+        });
+        tick_checked(&mut network, &mut checker, 20);
+        for (edge, metric) in [(3, INF), (4, 1), (4, 2), (3, 5)]{
+            network.set_link_metric(&edge, metric);
+            tick_checked(&mut network, &mut checker, 20);
+        }
+    }
+}
+
+#[test]
+fn reports_minimal_loop(){
+    let mut network = common::graphs::vnet_simple_weighted();
+    network.tick_n(10);
+
+    // make 3 forward to 1, which forwards to 2, which forwards back to 3
+    for (node, next_hop, link) in [("1", "2", 0), ("2", "3", 2), ("3", "1", 1)]{
+        let route = network.router_mut(&node.to_string()).unwrap().routes.get_mut("5").unwrap();
+        route.next_hop = next_hop.to_string();
+        route.link = link;
+    }
+
+    let violations = InvariantChecker::new().check(&network.routers);
+    let loops: Vec<_> = violations.iter().filter_map(|x| match x{
+        Violation::ForwardingLoop { dest, path } => Some((dest.as_str(), path.clone())),
+        _ => None
+    }).collect();
+    assert_eq!(loops, vec![("5", vec!["1".to_string(), "2".to_string(), "3".to_string(), "1".to_string()])]);
+}
+
+#[test]
+fn reports_missing_link(){
+    let mut network = common::graphs::vnet_simple_weighted();
+    network.tick_n(10);
+    network.router_mut(&"1".to_string()).unwrap().links.remove(&1);
+
+    let violations = InvariantChecker::new().check(&network.routers);
+    assert!(violations.iter().any(|x| matches!(x, Violation::MissingLink { node, dest, link: 1 } if node == "1" && dest == "3")));
+}
+
+#[test]
+fn router_reports_inconsistencies(){
+    let mut network = common::graphs::vnet_simple_weighted();
+    network.tick_n(10);
+    let router = network.router_mut(&"1".to_string()).unwrap();
+    assert!(router.check_invariants().is_empty());
+
+    router.routes.get_mut("2").unwrap().next_hop = "3".to_string();
+    let route = router.routes.get_mut("3").unwrap();
+    route.fd = route.metric + 1;
+    router.links.remove(&0);
+    router.seqno_requests.insert("7".to_string(), 1);
+    let mut route = router.routes["4"].clone();
+    route.retracted = false;
+    router.routes.insert("1".to_string(), route);
+
+    let violations = router.check_invariants();
+    assert!(violations.iter().any(|x| matches!(x, ConsistencyViolation::MissingLink { source, link: 0 } if source == "2")));
+    assert!(violations.iter().any(|x| matches!(x, ConsistencyViolation::FeasibilityDistanceAboveMetric { source, .. } if source == "3")));
+    assert!(violations.iter().any(|x| matches!(x, ConsistencyViolation::UnknownSeqnoRequest { source } if source == "7")));
+    assert!(violations.iter().any(|x| matches!(x, ConsistencyViolation::RouteToSelf)));
+
+    // retracted routes may refer to removed links
+    network.tick();
+    let router = network.router_mut(&"1".to_string()).unwrap();
+    router.routes.remove("1");
+    router.seqno_requests.clear();
+    router.routes.get_mut("3").unwrap().fd = 1;
+    assert!(router.check_invariants().iter().all(|x| !matches!(x, ConsistencyViolation::MissingLink { .. })));
+}
+
+#[test]
+fn reports_wrong_next_hop(){
+    let mut network = common::graphs::vnet_simple_weighted();
+    network.tick_n(10);
+    let router = network.router_mut(&"1".to_string()).unwrap();
+    router.routes.get_mut("2").unwrap().next_hop = "3".to_string();
+    let violations = router.check_invariants();
+    assert!(matches!(&violations[..], [ConsistencyViolation::WrongNextHop { source, next_hop, neighbour }] if source == "2" && next_hop == "3" && neighbour == "2"));
+}
+
+#[test]
+fn saturated_metric_does_not_loop(){
+    let mut network = common::graphs::vnet_fragile_network();
+    let mut checker = InvariantChecker::new();
+    tick_checked(&mut network, &mut checker, 10);
+    // the metrics through 1 - 4 saturate at INF - 1, so a route that loops back through 1 has the same metric as its fd
+    network.set_link_metric(&3, INF - 1);
+    tick_checked(&mut network, &mut checker, 6);
+    network.set_link_metric(&3, INF);
+    tick_checked(&mut network, &mut checker, 10);
+}
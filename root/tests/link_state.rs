@@ -0,0 +1,130 @@
+use root::router::{Router, INF};
+use root::util::seqno_less_than;
+use root::sim::Simulation;
+use crate::common::create;
+
+mod common;
+use common::NetworkExt;
+
+common::test_system!(LinkStateSystem {
+    const LINK_STATE: bool = true;
+});
+
+type LinkStateNetwork = Simulation<LinkStateSystem>;
+
+/// 1 - 2 - 3 - 4 - 1, with 4 - 1 expensive
+fn square() -> LinkStateNetwork{
+    let mut network = create(
+        &["1", "2", "3", "4"],
+        &[(0, "1", "2", 1), (1, "2", "3", 1), (2, "3", "4", 1), (3, "4", "1", 10)]
+    );
+    network.tick_n(10);
+    network
+}
+
+fn edges(network: &LinkStateNetwork, node: &str) -> Vec<(String, String, u16)>{
+    let mut edges = network.router(&node.to_string()).unwrap().topology_edges();
+    edges.sort();
+    edges
+}
+
+fn expected(links: &[(&str, &str, u16)]) -> Vec<(String, String, u16)>{
+    let mut edges = Vec::new();
+    for (a, b, metric) in links{
+        edges.push((a.to_string(), b.to_string(), *metric));
+        edges.push((b.to_string(), a.to_string(), *metric));
+    }
+    edges.sort();
+    edges
+}
+
+#[test]
+fn every_node_knows_the_topology(){
+    let network = square();
+    for node in ["1", "2", "3", "4"]{
+        assert_eq!(edges(&network, node), expected(&[("1", "2", 1), ("2", "3", 1), ("3", "4", 1), ("4", "1", 10)]));
+    }
+    // forwarding still uses the distance-vector routes
+    assert_eq!(network.get_metric_to("1", "4"), 3);
+    assert!(network.violations.is_empty());
+}
+
+#[test]
+fn metric_changes_are_flooded(){
+    let mut network = square();
+    let router = network.router(&"1".to_string()).unwrap();
+    let (seqno, link_state_seqno) = (router.seqno, router.link_state_seqno);
+    network.set_link_metric(&3, 2);
+    network.tick_n(5);
+    // the link state has its own seqno, the routes of the node are not refreshed
+    let router = network.router(&"1".to_string()).unwrap();
+    assert_eq!(router.seqno, seqno);
+    assert_ne!(router.link_state_seqno, link_state_seqno);
+    assert_eq!(edges(&network, "2"), expected(&[("1", "2", 1), ("2", "3", 1), ("3", "4", 1), ("4", "1", 2)]));
+    assert_eq!(network.get_metric_to("1", "4"), 2);
+}
+
+#[test]
+fn metric_jitter_waits_for_the_full_update(){
+    let mut router = common::router::<LinkStateSystem>("1", &[(0, "2"), (1, "3")]);
+    router.full_update();
+    let seqno = router.seqno;
+    let link_state_seqno = router.link_state_seqno;
+
+    // every update sees a new metric, but the link state seqno changes once per round
+    for metric in [2, 3, 4]{
+        router.set_link_metric(&0, metric);
+        router.update();
+        assert_eq!(router.link_state_seqno, link_state_seqno);
+    }
+    router.full_update();
+    assert_eq!(router.link_state_seqno, link_state_seqno + 1);
+
+    // losing a link is flooded right away
+    router.set_link_metric(&1, INF);
+    router.update();
+    assert_eq!(router.link_state_seqno, link_state_seqno + 2);
+    assert_eq!(router.seqno, seqno);
+}
+
+#[test]
+fn restarted_node_replaces_its_old_link_state(){
+    let mut network = square();
+    for metric in [5, 10]{
+        network.set_link_metric(&3, metric);
+        network.tick_n(5);
+    }
+    let old = network.router(&"1".to_string()).unwrap().link_state_seqno;
+
+    // the node comes back with its seqno reset, and finds its old link state still flooded
+    let addr = "1".to_string();
+    let mut restarted = Router::new(addr.clone());
+    restarted.links = network.router(&addr).unwrap().links.clone();
+    *network.router_mut(&addr).unwrap() = restarted;
+    network.set_link_metric(&3, 7);
+    network.tick_n(10);
+    assert!(seqno_less_than(old, network.router(&addr).unwrap().link_state_seqno));
+    for node in ["1", "2", "3", "4"]{
+        assert_eq!(edges(&network, node), expected(&[("1", "2", 1), ("2", "3", 1), ("3", "4", 1), ("4", "1", 7)]));
+    }
+}
+
+#[test]
+fn failed_links_are_removed(){
+    let mut network = square();
+    network.set_link_metric(&1, INF);
+    network.tick_n(10);
+    for node in ["1", "2", "3", "4"]{
+        assert_eq!(edges(&network, node), expected(&[("1", "2", 1), ("3", "4", 1), ("4", "1", 10)]));
+    }
+}
+
+#[test]
+fn unreachable_nodes_are_forgotten(){
+    let mut network = square();
+    network.remove_link(&0);
+    network.remove_link(&3);
+    network.tick_n(10);
+    assert_eq!(edges(&network, "1"), []);
+    assert_eq!(edges(&network, "3"), expected(&[("2", "3", 1), ("3", "4", 1)]));
+}
@@ -0,0 +1,54 @@
+use root::router::INF;
+use root::sim::LinkFaults;
+use crate::common::VirtualNetwork;
+
+mod common;
+use common::NetworkExt;
+
+const SEEDS: u64 = 20;
+
+fn lossy_network(seed: u64, faults: LinkFaults) -> VirtualNetwork{
+    let mut network = common::graphs::vnet_simple_weighted();
+    network.set_seed(seed);
+    network.tick_n(10); // converge before the links become lossy
+    network.set_all_link_faults(faults);
+    network
+}
+
+fn harsh() -> LinkFaults{
+    LinkFaults{
+        loss: 0.3,
+        latency: 2,
+        duplication: 0.2,
+        reordering: 0.3,
+    }
+}
+
+#[test]
+fn retraction_under_loss(){
+    for seed in 0..SEEDS{
+        let mut network = lossy_network(seed, harsh());
+        network.update_edge(3, INF);
+        network.tick_n(40);
+        assert_eq!(network.get_metric_to("1", "5"), 9, "seed {seed}");
+        assert_eq!(network.get_next_hop("1", "5"), "3", "seed {seed}");
+        assert!(network.errors.is_empty());
+        assert!(network.violations.is_empty(), "{:?}", network.violations);
+    }
+}
+
+#[test]
+fn starvation_recovery_under_loss(){
+    for seed in 0..SEEDS{
+        let mut network = lossy_network(seed, harsh());
+        network.update_edge(4, 1);
+        network.tick_n(40);
+        assert_eq!(network.get_metric_to("1", "5"), 3, "seed {seed}");
+
+        // increasing the metric starves 1, which has to recover through seqno requests
+        network.update_edge(4, 2);
+        network.tick_n(40);
+        assert_eq!(network.get_metric_to("1", "5"), 4, "seed {seed}");
+        assert_eq!(network.get_next_hop("1", "5"), "3", "seed {seed}");
+    }
+}
@@ -0,0 +1,123 @@
+use root::feedback::RoutingError;
+use root::framework::{MACSystem, RootData, RoutingSystem, MAC};
+use root::router::{DummyMAC, Router, INF};
+use root::sim::model::{ModelChecker, ModelFailure};
+use crate::common::{create, VirtualNetwork, VirtualSystem};
+
+mod common;
+
+/// explores the network, and fails with the shortest counterexample if there is one
+fn check(network: &VirtualNetwork, checker: &ModelChecker<VirtualSystem>){
+    let report = checker.run(network);
+    if let Some(counterexample) = report.counterexample{
+        panic!("{:#?}", counterexample);
+    }
+    assert!(report.complete, "explored {} states without finishing", report.states);
+}
+
+fn triangle() -> VirtualNetwork{
+    create(
+        &["1", "2", "3"],
+        &[
+            (0, "1", "2", 1),
+            (1, "2", "3", 1),
+            (2, "1", "3", 5),
+        ]
+    )
+}
+
+/// a converged triangle, with nothing in flight
+fn converged_triangle(seqno: u16) -> VirtualNetwork{
+    let mut network = triangle();
+    for router in &mut network.routers{
+        router.seqno = seqno;
+    }
+    network.tick_n(10);
+    network.packets.clear();
+    network
+}
+
+#[test]
+fn triangle_from_scratch(){
+    let mut checker = ModelChecker::new();
+    checker.max_updates = 3;
+    checker.max_losses = 0;
+    check(&triangle(), &checker);
+}
+
+#[test]
+fn triangle_link_failure(){
+    let mut checker = ModelChecker::new();
+    checker.max_updates = 0;
+    checker.link_changes = vec![(0, INF)];
+    check(&converged_triangle(0), &checker);
+}
+
+#[test]
+fn triangle_link_failure_with_reordering(){
+    let mut checker = ModelChecker::new();
+    checker.max_updates = 0;
+    checker.max_losses = 0;
+    checker.reordering = true;
+    checker.link_changes = vec![(0, INF)];
+    check(&converged_triangle(0), &checker);
+}
+
+#[test]
+fn seqno_wraparound(){
+    // losing the direct link starves the routes between 1 and 2, their new seqno wraps around to 0
+    let network = converged_triangle(u16::MAX);
+    let mut checker = ModelChecker::new();
+    checker.max_updates = 0;
+    checker.link_changes = vec![(0, INF)];
+    check(&network, &checker);
+
+    let mut network = network;
+    network.set_link_metric(&0, INF);
+    network.tick_n(10);
+    assert_eq!(network.route(&"2".to_string(), &"1".to_string()).unwrap().source.data.seqno, 0);
+}
+
+#[test]
+fn reports_bounded_exploration(){
+    let mut checker = ModelChecker::new();
+    checker.max_states = 10;
+    let report = checker.run(&common::graphs::vnet_simple_weighted());
+    assert!(!report.complete);
+    assert_eq!(report.states, 10);
+    assert!(report.counterexample.is_none());
+}
+
+/// rejects everything signed by node 3, as if its key was misconfigured
+#[derive(Default)]
+struct DistrustThree {}
+
+impl<T: RoutingSystem<NodeAddress = String> + ?Sized> MACSystem<T> for DistrustThree {
+    type MACSignatureType<V: RootData> = DummyMAC<V>;
+    fn sign<V: RootData>(&self, data: V, _router: &Router<T>) -> DummyMAC<V>{
+        data.into()
+    }
+
+    fn validate<V: RootData>(&self, _sig: &MAC<V, T>, subject: &String) -> bool {
+        subject != "3"
+    }
+}
+
+common::test_system!(DistrustSystem {
+    type MACSystem = DistrustThree;
+});
+
+#[test]
+fn router_errors_are_counterexamples(){
+    let network = create::<DistrustSystem>(&["1", "2", "3"], &[(0, "1", "2", 1), (1, "2", "3", 1)]);
+    let mut checker = ModelChecker::new();
+    checker.max_losses = 0;
+    let counterexample = checker.run(&network).counterexample.expect("the forged routes of 3 were not reported");
+    match counterexample.failure{
+        ModelFailure::RouterError(node, RoutingError::MACValidationFail{ link }) => {
+            assert_eq!(node, "2");
+            assert_eq!(link, 1);
+        }
+        failure => panic!("{:?}", failure),
+    }
+}
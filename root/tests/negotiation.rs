@@ -0,0 +1,151 @@
+use root::concepts::neighbour::NeighbourProtocol;
+use root::concepts::packet::{Capabilities, OutboundPacket, Packet, RouteUpdate, PROTOCOL_VERSION};
+use root::concepts::route::Source;
+use root::framework::RoutingSystem;
+use root::router::{DummyMAC, Router, INF};
+use crate::common::VirtualNetwork;
+
+mod common;
+
+common::test_system!(AckSystem {
+    const REQUEST_ACKS: bool = true;
+});
+
+fn router(addr: &str, neigh: &str) -> Router<AckSystem>{
+    common::router(addr, &[(0, neigh)])
+}
+
+fn hellos(packets: &[OutboundPacket<AckSystem>]) -> usize{
+    packets.iter().filter(|x| matches!(x.packet.data, Packet::Hello { .. })).count()
+}
+
+fn deliver(router: &mut Router<AckSystem>, from: &str, packets: Vec<OutboundPacket<AckSystem>>){
+    common::deliver(router, from, packets);
+    router.update();
+}
+
+/// two urgent updates for different sources, towards the only neighbour
+fn write_urgent_updates(router: &mut Router<AckSystem>){
+    for source in ["3", "4"]{
+        router.write_broadcast_packet(&DummyMAC::from(Packet::UrgentRouteUpdate(RouteUpdate{
+            source: DummyMAC::from(Source{ addr: source.to_string(), seqno: 0, injected: false }),
+            metric: INF,
+            hop_count: 0,
+        })));
+    }
+}
+
+#[test]
+fn neighbours_exchange_hellos(){
+    let mut network: VirtualNetwork = common::create(&["1", "2"], &[(0, "1", "2", 1)]);
+    network.tick_n(3);
+    for router in &network.routers{
+        let neigh = &router.links[&0];
+        assert_eq!(neigh.version(), PROTOCOL_VERSION);
+        assert_eq!(neigh.capabilities(), Capabilities::ALL);
+    }
+}
+
+#[test]
+fn hellos_are_answered_once(){
+    let mut a = router("1", "2");
+    let mut b = router("2", "1");
+    a.update();
+    let packets = a.take_outbound_packets();
+    assert_eq!(hellos(&packets), 1);
+    deliver(&mut b, "1", packets);
+    // b answers, and does not send a hello of its own
+    let packets = b.take_outbound_packets();
+    assert_eq!(hellos(&packets), 1);
+    deliver(&mut a, "2", packets);
+    assert_eq!(hellos(&a.take_outbound_packets()), 0);
+    a.full_update();
+    b.full_update();
+    assert_eq!(hellos(&a.take_outbound_packets()), 0);
+    assert_eq!(hellos(&b.take_outbound_packets()), 0);
+}
+
+#[test]
+fn restarted_neighbour_is_answered(){
+    let mut a = router("1", "2");
+    let mut b = router("2", "1");
+    a.update();
+    deliver(&mut b, "1", a.take_outbound_packets());
+    deliver(&mut a, "2", b.take_outbound_packets());
+
+    // b restarts, and forgets about a
+    let mut b = router("2", "1");
+    b.update();
+    deliver(&mut a, "2", b.take_outbound_packets());
+    deliver(&mut b, "1", a.take_outbound_packets());
+    assert_eq!(b.links[&0].capabilities(), Capabilities::ALL);
+}
+
+#[test]
+fn silent_neighbour_is_spoken_to_as_version_0(){
+    let mut a = router("1", "2");
+    a.update();
+    let mut sent = hellos(&a.take_outbound_packets());
+    for _ in 0..10{
+        a.full_update();
+        sent += hellos(&a.take_outbound_packets());
+    }
+    assert_eq!(sent as u32, AckSystem::HELLO_ATTEMPTS);
+    assert_eq!(a.links[&0].version(), 0);
+
+    // merged updates and ack requests would not be understood
+    write_urgent_updates(&mut a);
+    let packets = a.take_outbound_packets();
+    assert_eq!(packets.len(), 2);
+    assert!(packets.iter().all(|x| matches!(x.packet.data, Packet::UrgentRouteUpdate(_))));
+    a.retransmit();
+    assert!(a.take_outbound_packets().is_empty());
+}
+
+#[test]
+fn packets_are_downgraded_to_capabilities(){
+    let mut a = router("1", "2");
+    a.links.get_mut(&0).unwrap().protocol = NeighbourProtocol::Known{
+        version: PROTOCOL_VERSION,
+        capabilities: Capabilities::MERGED_UPDATES,
+    };
+    a.add_class(1);
+    a.set_class_link_metric(1, &0, 1);
+    a.update();
+    a.take_outbound_packets();
+
+    write_urgent_updates(&mut a);
+    a.full_update();
+    let packets = a.take_outbound_packets();
+    assert!(packets.iter().any(|x| matches!(x.packet.data, Packet::MergedRouteUpdate { .. })));
+    assert!(packets.iter().all(|x| !matches!(x.packet.data, Packet::AckRequest { .. } | Packet::ClassPacket { .. })));
+}
+
+#[test]
+fn version_0_neighbour_only_gets_version_0_fields(){
+    let mut a = router("1", "2");
+    a.add_static_route("10".to_string(), 1, 200);
+    for (source, injected) in [("3", false), ("4", true)]{
+        a.write_broadcast_packet(&DummyMAC::from(Packet::UrgentRouteUpdate(RouteUpdate{
+            source: DummyMAC::from(Source{ addr: source.to_string(), seqno: 0, injected }),
+            metric: 5,
+            hop_count: 3,
+        })));
+    }
+    a.full_update();
+    let packets = a.take_outbound_packets();
+    assert_eq!(a.links[&0].version(), 0);
+
+    // the injected routes are left out, and the others are sent without a hop count, as version 0 would serialize them
+    let updates: Vec<_> = packets.iter().flat_map(|x| match &x.packet.data{
+        Packet::UrgentRouteUpdate(update) => vec![update.clone()],
+        Packet::BatchRouteUpdate { routes } => routes.clone(),
+        _ => vec![],
+    }).collect();
+    assert!(updates.iter().any(|x| x.source.data.addr == "3"));
+    assert!(updates.iter().all(|x| !x.source.data.injected && x.hop_count == 0));
+    for packet in &packets{
+        let json = serde_json::to_string(&packet.packet).unwrap();
+        assert!(!json.contains("hop_count") && !json.contains("injected"), "{json}");
+    }
+}
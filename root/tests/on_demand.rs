@@ -0,0 +1,104 @@
+use root::framework::RoutingSystem;
+use root::router::INF;
+use root::sim::Simulation;
+use root::sim::invariants::InvariantChecker;
+use crate::common::tick_checked;
+
+mod common;
+use common::NetworkExt;
+
+common::test_system!(OnDemandSystem {
+    const ON_DEMAND: bool = true;
+    const ROUTE_EXPIRY_ROUNDS: u64 = 5;
+});
+
+type OnDemandNetwork = Simulation<OnDemandSystem>;
+
+/// 1 - 2 - 3 - 4 - 5, where 1 is a gateway that every node knows about
+fn line() -> OnDemandNetwork{
+    let mut network: OnDemandNetwork = common::line(5);
+    for router in &mut network.routers{
+        router.proactive.insert("1".to_string());
+    }
+    network.tick_n(10);
+    network
+}
+
+fn has_route(network: &OnDemandNetwork, cur: &str, dest: &str) -> bool{
+    network.reachable_route(cur, dest).is_some()
+}
+
+fn request(network: &mut OnDemandNetwork, cur: &str, dest: &str) -> Option<(String, u16)>{
+    let router = network.router_mut(&cur.to_string()).unwrap();
+    router.request_route(&dest.to_string()).map(|route| (route.next_hop.clone(), route.metric))
+}
+
+#[test]
+fn only_proactive_destinations_are_advertised(){
+    let network = line();
+    for node in ["2", "3", "4", "5"]{
+        assert!(has_route(&network, node, "1"));
+    }
+    assert!(!has_route(&network, "1", "5"));
+    assert!(!has_route(&network, "5", "3"));
+    assert!(network.violations.is_empty());
+}
+
+#[test]
+fn routes_are_discovered_on_demand(){
+    let mut network = line();
+    assert_eq!(request(&mut network, "1", "5"), None);
+    network.tick_n(10);
+    assert_eq!(request(&mut network, "1", "5"), Some(("2".to_string(), 4)));
+    // the routers on the way forwarded the answer
+    assert!(has_route(&network, "3", "5"));
+    assert!(network.violations.is_empty());
+    assert!(network.errors.is_empty());
+}
+
+#[test]
+fn unused_routes_expire(){
+    let mut network = line();
+    request(&mut network, "1", "5");
+    network.tick_n(10);
+    for _ in 0..10{
+        assert!(request(&mut network, "1", "5").is_some());
+        network.tick();
+    }
+    // the routers on the way expire first, and retract the route towards 1
+    network.tick_n(2 * OnDemandSystem::ROUTE_EXPIRY_ROUNDS as usize);
+    assert!(!has_route(&network, "1", "5"));
+    assert!(!has_route(&network, "3", "5"));
+    // proactive routes never expire
+    assert!(has_route(&network, "5", "1"));
+
+    // the route can be discovered again
+    request(&mut network, "1", "5");
+    network.tick_n(10);
+    assert_eq!(request(&mut network, "1", "5"), Some(("2".to_string(), 4)));
+}
+
+#[test]
+fn expired_routes_are_retracted(){
+    let mut network = line();
+    let mut checker = InvariantChecker::new();
+    request(&mut network, "1", "5");
+    tick_checked(&mut network, &mut checker, 10);
+
+    // 1 keeps sending to 5, while the routers on the way stop using their own routes, which expire.
+    // 1 may only route into a router without a route until the retraction arrives
+    let mut black_hole = 0;
+    for _ in 0..3 * OnDemandSystem::ROUTE_EXPIRY_ROUNDS{
+        match request(&mut network, "1", "5").filter(|(_, metric)| *metric != INF){
+            Some((next_hop, _)) if !has_route(&network, &next_hop, "5") => black_hole += 1,
+            _ => black_hole = 0,
+        }
+        assert!(black_hole <= 1, "tick {}: 1 routes to 5 through a router without a route", network.time);
+        tick_checked(&mut network, &mut checker, 1);
+    }
+
+    // 2 discovers the route again
+    request(&mut network, "2", "5");
+    tick_checked(&mut network, &mut checker, 10);
+    assert_eq!(request(&mut network, "2", "5"), Some(("3".to_string(), 3)));
+}
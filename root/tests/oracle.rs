@@ -0,0 +1,76 @@
+use root::router::INF;
+use root::sim::oracle::ShortestPaths;
+use root::sim::SimRng;
+use crate::common::VirtualNetwork;
+
+mod common;
+
+/// builds a random network the same way as graph-gen, nodes are numbered from 1 and metrics are 1..100
+fn random_network(seed: u64, nodes: u64, edges: usize) -> VirtualNetwork{
+    let mut rng = SimRng::new(seed);
+    let mut network = VirtualNetwork::new();
+    for node in 1..nodes{
+        network.add_router(node.to_string());
+    }
+    for link in 0..edges{
+        let a = rng.next_u64() % (nodes - 1) + 1;
+        let b = rng.next_u64() % (nodes - 1) + 1;
+        if a == b{
+            continue;
+        }
+        let metric = (rng.next_u64() % 99 + 1) as u16;
+        network.add_link(link as i32, a.to_string(), b.to_string(), metric);
+    }
+    network
+}
+
+#[test]
+fn existing_graphs_are_optimal(){
+    for mut network in [common::graphs::vnet_simple_weighted(), common::graphs::vnet_fragile_network()]{
+        network.tick_n(20);
+        ShortestPaths::from_simulation(&network).assert_optimal(&network.routers);
+    }
+}
+
+#[test]
+fn random_graphs_are_optimal(){
+    for seed in 0..5{
+        let mut network = random_network(seed, 50, 100);
+        network.tick_n(30);
+        ShortestPaths::from_simulation(&network).assert_optimal(&network.routers);
+    }
+}
+
+#[test]
+fn random_graphs_are_optimal_after_changes(){
+    for seed in 0..5{
+        let mut network = random_network(seed, 30, 60);
+        network.tick_n(30);
+        let mut rng = SimRng::new(seed + 1000);
+        for _ in 0..5{
+            let link = network.links[(rng.next_u64() % network.links.len() as u64) as usize].link;
+            let metric = if rng.chance(0.3) { INF } else { (rng.next_u64() % 99 + 1) as u16 };
+            network.set_link_metric(&link, metric);
+        }
+        network.tick_n(40);
+        ShortestPaths::from_simulation(&network).assert_optimal(&network.routers);
+    }
+}
+
+#[test]
+fn reports_suboptimal_route(){
+    let mut network = common::graphs::vnet_simple_weighted();
+    network.tick_n(20);
+    let oracle = ShortestPaths::from_simulation(&network);
+    assert!(oracle.check(&network.routers).is_empty());
+
+    // the routers have not heard about the new link yet
+    network.add_link(7, "1".to_string(), "5".to_string(), 1);
+    let oracle = ShortestPaths::from_simulation(&network);
+    assert_eq!(oracle.metric(&"1".to_string(), &"5".to_string()), 1);
+    let mismatches = oracle.check(&network.routers);
+    assert!(mismatches.iter().any(|x| x.node == "1" && x.dest == "5" && x.expected_metric == 1));
+
+    network.tick_n(20);
+    ShortestPaths::from_simulation(&network).assert_optimal(&network.routers);
+}
@@ -0,0 +1,88 @@
+use root::concepts::packet::{Capabilities, Packet, PacketPriority, RouteUpdate};
+use root::concepts::route::Source;
+use root::router::{DummyMAC, Router, INF};
+use crate::common::{assume_negotiated, VirtualNetwork, VirtualSystem};
+
+mod common;
+
+fn router(links: &[(i32, &str)]) -> Router<VirtualSystem>{
+    let mut router = common::router("1", links);
+    assume_negotiated(&mut router, Capabilities::ALL);
+    router
+}
+
+fn update(source: &str, seqno: u16, metric: u16) -> RouteUpdate<VirtualSystem>{
+    RouteUpdate{
+        source: DummyMAC::from(Source{ addr: source.to_string(), seqno, injected: false }),
+        metric,
+        hop_count: 1,
+    }
+}
+
+fn urgent(router: &mut Router<VirtualSystem>, source: &str, metric: u16){
+    router.write_broadcast_packet(&DummyMAC::from(Packet::UrgentRouteUpdate(update(source, 0, metric))));
+}
+
+#[test]
+fn superseded_updates_are_dropped(){
+    let mut router = router(&[(0, "2")]);
+    urgent(&mut router, "3", INF);
+    urgent(&mut router, "3", 4);
+    let packets = router.take_outbound_packets();
+    assert_eq!(packets.len(), 1);
+    let Packet::UrgentRouteUpdate(update) = &packets[0].packet.data else {
+        panic!("expected a single urgent update");
+    };
+    assert_eq!(update.metric, 4);
+    assert!(router.outbound_packets().is_empty());
+}
+
+#[test]
+fn urgent_updates_are_merged_per_link(){
+    let mut router = router(&[(0, "2"), (1, "3")]);
+    urgent(&mut router, "4", 1);
+    urgent(&mut router, "5", 2);
+    urgent(&mut router, "4", 3);
+    let packets = router.take_outbound_packets();
+    assert_eq!(packets.len(), 2);
+    for packet in &packets{
+        let Packet::MergedRouteUpdate { routes } = &packet.packet.data else {
+            panic!("expected a merged update");
+        };
+        let routes: Vec<_> = routes.iter().map(|x| (x.source.data.addr.as_str(), x.metric)).collect();
+        assert_eq!(routes, [("4", 3), ("5", 2)]);
+    }
+}
+
+#[test]
+fn urgent_packets_are_sent_first(){
+    let mut router = router(&[(0, "2")]);
+    router.full_update();
+    urgent(&mut router, "3", INF);
+    router.write_broadcast_packet(&DummyMAC::from(Packet::SeqnoRequest{ source: "4".to_string(), seqno: 1 }));
+    let priorities: Vec<_> = router.take_outbound_packets().iter().map(|x| x.priority()).collect();
+    assert_eq!(priorities, [PacketPriority::Urgent, PacketPriority::Urgent, PacketPriority::Periodic]);
+}
+
+#[test]
+fn merged_updates_are_handled_separately(){
+    // 1 - 2 - 3 - 4, 2 loses its routes to 3 and 4 at once
+    let mut network: VirtualNetwork = common::create(
+        &["1", "2", "3", "4"],
+        &[(0, "1", "2", 1), (1, "2", "3", 1), (2, "3", "4", 1)]
+    );
+    network.tick_n(10);
+    network.set_link_metric(&1, INF);
+    network.router_mut(&"2".to_string()).unwrap().update();
+    let packets = network.router_mut(&"2".to_string()).unwrap().take_outbound_packets();
+    let to_1: Vec<_> = packets.iter().filter(|x| x.dest == "1").collect();
+    assert_eq!(to_1.len(), 1);
+    assert!(matches!(&to_1[0].packet.data, Packet::MergedRouteUpdate { routes } if routes.len() == 2));
+
+    let router = network.router_mut(&"1".to_string()).unwrap();
+    router.handle_packet(&to_1[0].packet, &0, &"2".to_string()).unwrap();
+    router.update();
+    for dest in ["3", "4"]{
+        assert_eq!(router.routes[&dest.to_string()].metric, INF);
+    }
+}
@@ -0,0 +1,207 @@
+use std::io::Cursor;
+use root::concepts::neighbour::Neighbour;
+use root::concepts::packet::OutboundPacket;
+use root::record::{RecordingRouter, Replay, RouterInput};
+use root::router::{Router, INF};
+use crate::common::VirtualSystem;
+
+mod common;
+
+type Recorder = RecordingRouter<VirtualSystem, Vec<u8>>;
+
+/// 1 - 2 - 3 - 1, where 2 is recorded
+struct Network{
+    recorded: Recorder,
+    others: Vec<Router<VirtualSystem>>,
+    /// the packets drained from the recorded router, in order
+    outputs: Vec<String>,
+}
+
+impl Network{
+    fn new(recorded: Router<VirtualSystem>) -> Self{
+        let mut others = vec![Router::new("1".to_string()), Router::new("3".to_string())];
+        for (router, links) in others.iter_mut().zip([[(0, "2"), (2, "3")], [(1, "2"), (2, "1")]]){
+            for (link, neigh) in links{
+                let mut neighbour = Neighbour::new(neigh.to_string());
+                neighbour.metric = 1;
+                router.links.insert(link, neighbour);
+            }
+        }
+        Self{
+            recorded: RecordingRouter::new(recorded, Vec::new()).unwrap(),
+            others,
+            outputs: Vec::new(),
+        }
+    }
+
+    fn capture(&mut self) -> Vec<OutboundPacket<VirtualSystem>>{
+        let packets = self.recorded.take_outbound_packets();
+        self.outputs.push(serde_json::to_string(&packets).unwrap());
+        packets
+    }
+
+    fn round(&mut self){
+        self.recorded.full_update();
+        let mut packets = self.capture();
+        for router in &mut self.others{
+            router.full_update();
+            packets.append(&mut router.take_outbound_packets());
+        }
+        for OutboundPacket{link, dest, packet} in packets{
+            if dest == "2"{
+                // the link may have been removed
+                let Some(neigh) = self.recorded.links.get(&link).map(|x| x.addr.clone()) else {
+                    continue;
+                };
+                self.recorded.handle_packet(&packet, &link, &neigh).unwrap();
+                self.capture();
+                self.recorded.update();
+                self.capture();
+            }
+            else if let Some(router) = self.others.iter_mut().find(|r| r.address == dest){
+                let neigh = router.links[&link].addr.clone();
+                router.handle_packet(&packet, &link, &neigh).unwrap();
+                router.update();
+            }
+        }
+    }
+
+    /// starts a new recording of the recorded router, in its current state
+    fn restart_recording(&mut self){
+        let placeholder = RecordingRouter::new(Router::new("2".to_string()), Vec::new()).unwrap();
+        let (router, _) = std::mem::replace(&mut self.recorded, placeholder).into_inner();
+        self.recorded = RecordingRouter::new(router, Vec::new()).unwrap();
+        self.outputs.clear();
+    }
+
+    fn set_link_metric(&mut self, link: i32, metric: u16){
+        for router in &mut self.others{
+            router.set_link_metric(&link, metric);
+        }
+        self.recorded.set_link_metric(&link, metric);
+        self.capture();
+    }
+}
+
+fn replay_matches(network: Network){
+    let (router, log) = network.recorded.into_inner();
+    let mut replay: Replay<VirtualSystem, _> = Replay::new(Cursor::new(log)).unwrap();
+    let mut outputs = Vec::new();
+    while let Some(step) = replay.step().unwrap(){
+        assert!(step.error.is_none());
+        if matches!(step.input, RouterInput::TakeOutboundPackets){
+            outputs.push(serde_json::to_string(&step.outbound_packets).unwrap());
+        }
+        else{
+            assert!(step.outbound_packets.is_empty());
+        }
+    }
+    assert_eq!(outputs, network.outputs);
+    assert_eq!(serde_json::to_string(&replay.router).unwrap(), serde_json::to_string(&router).unwrap());
+    assert_eq!(serde_json::to_string(&replay.router.routes).unwrap(), serde_json::to_string(&router.routes).unwrap());
+    assert_eq!(serde_json::to_string(&replay.router.links).unwrap(), serde_json::to_string(&router.links).unwrap());
+}
+
+#[test]
+fn replay_from_scratch(){
+    let mut network = Network::new(Router::new("2".to_string()));
+    network.recorded.add_link(0, "1".to_string(), 1);
+    network.capture();
+    network.recorded.add_link(1, "3".to_string(), 1);
+    network.capture();
+    for _ in 0..5{
+        network.round();
+    }
+    network.set_link_metric(0, INF);
+    for _ in 0..5{
+        network.round();
+    }
+    assert_eq!(network.recorded.routes[&"1".to_string()].next_hop, "3");
+    replay_matches(network);
+}
+
+#[test]
+fn replay_from_running_router(){
+    let mut network = Network::new(Router::new("2".to_string()));
+    network.recorded.add_link(0, "1".to_string(), 1);
+    network.recorded.add_link(1, "3".to_string(), 1);
+    for _ in 0..5{
+        network.round();
+    }
+
+    // start recording a router that already has routes
+    assert!(!network.recorded.routes.is_empty());
+    network.restart_recording();
+
+    network.set_link_metric(1, 10);
+    for _ in 0..5{
+        network.round();
+    }
+    network.recorded.remove_link(&1);
+    network.capture();
+    for _ in 0..3{
+        network.round();
+    }
+    replay_matches(network);
+}
+
+#[test]
+fn log_is_json_lines(){
+    let mut recorder = RecordingRouter::<VirtualSystem, _>::new(Router::new("2".to_string()), Vec::new()).unwrap();
+    recorder.add_link(0, "1".to_string(), 5);
+    recorder.full_update();
+    let (_, log) = recorder.into_inner();
+    let log = String::from_utf8(log).unwrap();
+    let lines: Vec<&str> = log.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert!(matches!(serde_json::from_str(lines[1]).unwrap(), RouterInput::<VirtualSystem>::AddLink{link: 0, metric: 5, ..}));
+    assert!(matches!(serde_json::from_str(lines[2]).unwrap(), RouterInput::<VirtualSystem>::FullUpdate));
+}
+
+#[test]
+fn batched_drains_are_replayed(){
+    let mut network = Network::new(Router::new("2".to_string()));
+    network.recorded.add_link(0, "1".to_string(), 1);
+    network.recorded.add_link(1, "3".to_string(), 1);
+    for _ in 0..3{
+        network.round();
+    }
+
+    // several inputs queue packets before a single drain, which coalesces the urgent updates
+    network.recorded.set_link_metric(&0, 5);
+    network.recorded.update();
+    network.recorded.set_link_metric(&0, 7);
+    network.recorded.update();
+    network.recorded.retransmit();
+    network.capture();
+    for _ in 0..3{
+        network.round();
+    }
+    replay_matches(network);
+}
+
+#[test]
+fn every_input_is_replayed(){
+    let mut network = Network::new(Router::new("2".to_string()));
+    network.recorded.add_link(0, "1".to_string(), 1);
+    network.recorded.add_link(1, "3".to_string(), 1);
+    network.round();
+
+    network.recorded.add_static_route("static".to_string(), 3, 1);
+    network.recorded.serve_anycast("anycast".to_string());
+    network.recorded.set_attributes([("role".to_string(), "gateway".to_string())].into());
+    network.recorded.add_class(1);
+    network.recorded.set_class_link_metric(1, &0, 4);
+    network.recorded.write_extension(&0, ());
+    network.recorded.broadcast_extension(());
+    network.recorded.request_route(&"1".to_string());
+    network.capture();
+    for _ in 0..3{
+        network.round();
+    }
+    network.recorded.remove_static_route(&"static".to_string());
+    network.recorded.stop_anycast(&"anycast".to_string());
+    network.capture();
+    network.round();
+    replay_matches(network);
+}
@@ -0,0 +1,75 @@
+use root::framework::{RouteCandidate, RouteSelector};
+use root::router::INF;
+use root::sim::Simulation;
+use root::util::RootSet;
+use crate::common::{create, VirtualNetwork};
+
+mod common;
+use common::NetworkExt;
+
+/// prefers routes over wired links, then the lowest metric
+#[derive(Default)]
+struct PreferWired {
+    wired: RootSet<i32>,
+}
+
+impl RouteSelector<WiredSystem> for PreferWired {
+    fn prefer(&self, candidate: &RouteCandidate<WiredSystem>, selected: &RouteCandidate<WiredSystem>) -> bool {
+        let candidate_wired = self.wired.contains(candidate.link);
+        let selected_wired = self.wired.contains(selected.link);
+        if candidate_wired != selected_wired {
+            return candidate_wired;
+        }
+        candidate.metric < selected.metric
+    }
+}
+
+common::test_system!(WiredSystem {
+    type RouteSelector = PreferWired;
+});
+
+type WiredNetwork = Simulation<WiredSystem>;
+
+/// 1 - 3 over a slow wired link, and 1 - 2 - 3 over fast wireless links
+fn network() -> WiredNetwork{
+    let mut network: WiredNetwork = create(
+        &["1", "2", "3"],
+        &[(0, "1", "3", 10), (1, "1", "2", 1), (2, "2", "3", 1)]
+    );
+    for node in ["1", "3"]{
+        network.router_mut(&node.to_string()).unwrap().route_selector.wired.insert(0);
+    }
+    network
+}
+
+#[test]
+fn preferred_route_is_kept(){
+    let mut network = network();
+    network.tick_n(10);
+    // the route over 2 is feasible and shorter, but 1 prefers the wired link
+    assert_eq!(network.get_next_hop("1", "3"), "3");
+    assert_eq!(network.get_metric_to("1", "3"), 10);
+    // 2 has no wired links, and takes the shortest route
+    assert_eq!(network.get_next_hop("2", "1"), "1");
+    assert!(network.violations.is_empty());
+}
+
+#[test]
+fn falls_back_when_preferred_route_fails(){
+    let mut network = network();
+    network.tick_n(10);
+    network.set_link_metric(&0, INF);
+    network.tick_n(5);
+    assert_eq!(network.get_next_hop("1", "3"), "2");
+    assert_eq!(network.get_next_hop("3", "1"), "2");
+}
+
+#[test]
+fn default_selects_shortest_route(){
+    let mut network: VirtualNetwork = create(
+        &["1", "2", "3"],
+        &[(0, "1", "3", 10), (1, "1", "2", 1), (2, "2", "3", 1)]
+    );
+    network.tick_n(10);
+    assert_eq!(network.get_next_hop("1", "3"), "2");
+}
@@ -1,5 +1,6 @@
 
 mod common;
+use common::NetworkExt;
 
 #[test]
 fn simple_weighted_graph(){
@@ -0,0 +1,46 @@
+use root::router::INF;
+use crate::common::VirtualNetwork;
+
+mod common;
+use common::NetworkExt;
+
+const NODES: [&str; 5] = ["1", "2", "3", "4", "5"];
+
+fn assert_same_tables(a: &VirtualNetwork, b: &VirtualNetwork){
+    for cur in NODES{
+        for dest in NODES.iter().filter(|x| **x != cur){
+            assert_eq!(a.get_metric_to(cur, dest), b.get_metric_to(cur, dest));
+            assert_eq!(a.get_next_hop(cur, dest), b.get_next_hop(cur, dest));
+        }
+    }
+}
+
+#[test]
+fn freeze_restore(){
+    let mut network = common::graphs::vnet_simple_weighted();
+    network.tick_n(10);
+
+    let mut restored = VirtualNetwork::restore(&network.freeze()).unwrap();
+    assert_same_tables(&network, &restored);
+
+    // the restored network keeps routing like the original
+    network.update_edge(3, INF);
+    restored.update_edge(3, INF);
+    network.tick_n(5);
+    restored.tick_n(5);
+    assert_same_tables(&network, &restored);
+    assert_eq!(restored.get_metric_to("1", "5"), 9);
+}
+
+#[test]
+fn remove_link(){
+    let mut network = common::graphs::vnet_simple_weighted();
+    network.tick_n(10);
+    assert_eq!(network.get_next_hop("1", "3"), "3");
+
+    network.remove_link(&1);
+    network.tick_n(5);
+    assert_eq!(network.get_next_hop("1", "3"), "2");
+    assert_eq!(network.get_metric_to("1", "3"), 6);
+    assert!(network.errors.is_empty());
+}
@@ -1,6 +1,9 @@
+use root::concepts::packet::Packet;
 use root::router::INF;
+use crate::common::VirtualNetwork;
 
 mod common;
+use common::NetworkExt;
 
 #[test]
 fn seqno_request(){
@@ -23,4 +26,24 @@ fn seqno_request(){
     network.tick_n(3); // takes 3 ticks for the packet to travel 
     assert_eq!(network.get_metric_to("1", "5"), 4);
     assert_eq!(network.get_next_hop("1", "5"), "3");
-}
\ No newline at end of file
+}
+
+#[test]
+fn lost_seqno_request_is_forwarded_again(){
+    // 1 - 2 - 3 - 4
+    let mut network: VirtualNetwork = common::create(&["1", "2", "3", "4"], &[(0, "1", "2", 1), (1, "2", "3", 1), (2, "3", "4", 1)]);
+    network.tick_n(10);
+
+    // starve 1, and lose every seqno request on its way to 4
+    network.update_edge(0, 5);
+    for _ in 0..4{
+        network.tick();
+        network.packets.retain(|x| x.dest != "4" || !matches!(x.packet.data, Packet::SeqnoRequest { .. }));
+    }
+    assert_eq!(network.get_metric_to("1", "4"), INF);
+
+    // 1 keeps requesting the seqno, and the routers on the way have to forward the request again
+    network.tick_n(5);
+    assert_eq!(network.get_metric_to("1", "4"), 7);
+    assert!(network.errors.is_empty());
+}
@@ -0,0 +1,153 @@
+use root::feedback::RoutingWarning;
+use root::router::INF;
+use root::sim::invariants::InvariantChecker;
+use crate::common::{create, VirtualNetwork};
+
+mod common;
+use common::{tick_checked, NetworkExt};
+
+const DEFAULT: &str = "default";
+const PREFERRED: u8 = 1;
+const FALLBACK: u8 = 200;
+
+fn add_static(network: &mut VirtualNetwork, node: &str, dest: &str, metric: u16, distance: u8){
+    network.router_mut(&node.to_string()).unwrap().add_static_route(dest.to_string(), metric, distance);
+}
+
+fn is_active(network: &VirtualNetwork, node: &str, dest: &str) -> bool{
+    network.router(&node.to_string()).unwrap().static_routes[dest].active
+}
+
+fn desynchronized(network: &VirtualNetwork, node: &str) -> bool{
+    network.router(&node.to_string()).unwrap().warnings.iter().any(|w| matches!(w, RoutingWarning::DesynchronizedSeqno{..}))
+}
+
+/// x - 1 - 2 - 3
+fn line() -> VirtualNetwork{
+    let mut network = create(
+        &["x", "1", "2", "3"],
+        &[(0, "x", "1", 1), (1, "1", "2", 1), (2, "2", "3", 1)]
+    );
+    network.tick_n(10);
+    network
+}
+
+#[test]
+fn static_routes_are_advertised(){
+    let mut network = line();
+    add_static(&mut network, "1", DEFAULT, 5, PREFERRED);
+    network.tick_n(5);
+    assert_eq!(network.reachable_route("3", DEFAULT), Some(("2".to_string(), 7)));
+    assert_eq!(network.reachable_route("x", DEFAULT), Some(("1".to_string(), 6)));
+    assert!(network.route(&"1".to_string(), &DEFAULT.to_string()).is_none());
+    assert!(network.violations.is_empty());
+}
+
+#[test]
+fn best_injected_route_is_selected(){
+    let mut network = line();
+    add_static(&mut network, "x", DEFAULT, 10, PREFERRED);
+    add_static(&mut network, "3", DEFAULT, 1, PREFERRED);
+    network.tick_n(10);
+    assert_eq!(network.reachable_route("1", DEFAULT), Some(("2".to_string(), 3)));
+
+    network.router_mut(&"3".to_string()).unwrap().remove_static_route(&DEFAULT.to_string());
+    network.tick_n(10);
+    assert_eq!(network.reachable_route("1", DEFAULT), Some(("x".to_string(), 11)));
+    assert!(network.violations.is_empty());
+}
+
+#[test]
+fn removed_static_route_keeps_its_fd(){
+    let mut network = line();
+    let mut checker = InvariantChecker::new();
+    add_static(&mut network, "x", DEFAULT, 10, PREFERRED);
+    add_static(&mut network, "3", DEFAULT, 1, PREFERRED);
+    tick_checked(&mut network, &mut checker, 10);
+    assert_eq!(network.reachable_route("1", DEFAULT), Some(("2".to_string(), 3)));
+
+    // the stale routes through 3 must not be used by 2 while the retraction spreads
+    network.router_mut(&"3".to_string()).unwrap().remove_static_route(&DEFAULT.to_string());
+    tick_checked(&mut network, &mut checker, 15);
+    assert_eq!(network.reachable_route("3", DEFAULT), Some(("2".to_string(), 13)));
+    assert_eq!(network.reachable_route("1", DEFAULT), Some(("x".to_string(), 11)));
+}
+
+#[test]
+fn preferred_static_route_stays_local(){
+    let mut network = line();
+    add_static(&mut network, "2", "x", 1, PREFERRED);
+    network.tick_n(10);
+    assert!(is_active(&network, "2", "x"));
+    assert!(network.router(&"2".to_string()).unwrap().static_route_to(&"x".to_string()).is_some());
+    // x keeps its address, 2 does not advertise the static route as a competing origin
+    assert_eq!(network.reachable_route("2", "x"), Some(("1".to_string(), 2)));
+    assert_eq!(network.reachable_route("3", "x"), Some(("2".to_string(), 3)));
+    assert!(!desynchronized(&network, "x"));
+    assert!(network.violations.is_empty());
+    assert!(network.errors.is_empty());
+}
+
+#[test]
+fn preferred_static_route_is_advertised_while_node_is_down(){
+    let mut network = line();
+    add_static(&mut network, "2", "x", 1, PREFERRED);
+    network.tick_n(10);
+
+    network.set_link_metric(&0, INF);
+    network.tick_n(10);
+    assert_eq!(network.reachable_route("3", "x"), Some(("2".to_string(), 2)));
+
+    // x takes its address back, and 2 keeps using its static route
+    network.set_link_metric(&0, 1);
+    network.tick_n(15);
+    assert!(is_active(&network, "2", "x"));
+    assert_eq!(network.reachable_route("2", "x"), Some(("1".to_string(), 2)));
+    assert_eq!(network.reachable_route("3", "x"), Some(("2".to_string(), 3)));
+    assert!(!desynchronized(&network, "x"));
+    assert!(network.violations.is_empty());
+    assert!(network.errors.is_empty());
+}
+
+#[test]
+fn fallback_is_used_while_learned_route_is_down(){
+    let mut network = line();
+    add_static(&mut network, "2", "x", 5, FALLBACK);
+    network.tick_n(5);
+    assert!(!is_active(&network, "2", "x"));
+    assert_eq!(network.reachable_route("3", "x"), Some(("2".to_string(), 3)));
+
+    network.set_link_metric(&0, INF);
+    network.tick_n(10);
+    assert!(is_active(&network, "2", "x"));
+    assert_eq!(network.reachable_route("1", "x"), Some(("2".to_string(), 6)));
+
+    // x advertises itself again, and takes its address back
+    network.set_link_metric(&0, 1);
+    network.tick_n(15);
+    assert!(!is_active(&network, "2", "x"));
+    assert_eq!(network.reachable_route("3", "x"), Some(("2".to_string(), 3)));
+    assert_eq!(network.reachable_route("2", "x"), Some(("1".to_string(), 2)));
+    assert!(!desynchronized(&network, "x"));
+    assert!(network.violations.is_empty());
+    assert!(network.errors.is_empty());
+}
+
+#[test]
+fn node_reclaims_its_address_after_losing_its_seqno(){
+    let mut network = line();
+    add_static(&mut network, "2", "x", 5, FALLBACK);
+    network.set_link_metric(&0, INF);
+    network.tick_n(10);
+    assert!(is_active(&network, "2", "x"));
+
+    // x restarts, and only learns about the injected route to itself
+    network.router_mut(&"x".to_string()).unwrap().seqno = 0;
+    network.set_link_metric(&0, 1);
+    network.tick_n(15);
+    assert!(!is_active(&network, "2", "x"));
+    assert_eq!(network.reachable_route("2", "x"), Some(("1".to_string(), 2)));
+    assert!(!desynchronized(&network, "x"));
+    assert!(network.violations.is_empty());
+    assert!(network.errors.is_empty());
+}
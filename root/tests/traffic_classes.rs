@@ -0,0 +1,103 @@
+use root::router::INF;
+use crate::common::{create, VirtualNetwork};
+
+mod common;
+
+const LATENCY: u8 = 1;
+
+/// sets the metric of a link in a class, on both of its ends
+fn set_class_metric(network: &mut VirtualNetwork, link: i32, a: &str, b: &str, metric: u16){
+    for node in [a, b]{
+        network.router_mut(&node.to_string()).unwrap().set_class_link_metric(LATENCY, &link, metric);
+    }
+}
+
+fn next_hop(network: &VirtualNetwork, cur: &str, dest: &str, class: u8) -> String{
+    network.router(&cur.to_string()).unwrap().route_for(&dest.to_string(), class).unwrap().next_hop.clone()
+}
+
+/// 1 - 2 - 4 is cheap by default, 1 - 3 - 4 has the least latency
+fn square() -> VirtualNetwork{
+    let mut network = create(
+        &["1", "2", "3", "4"],
+        &[(0, "1", "2", 1), (1, "2", "4", 1), (2, "1", "3", 5), (3, "3", "4", 5)]
+    );
+    for router in &mut network.routers{
+        router.add_class(LATENCY);
+    }
+    set_class_metric(&mut network, 0, "1", "2", 10);
+    set_class_metric(&mut network, 1, "2", "4", 10);
+    set_class_metric(&mut network, 2, "1", "3", 1);
+    set_class_metric(&mut network, 3, "3", "4", 1);
+    network.tick_n(10);
+    network
+}
+
+#[test]
+fn classes_are_routed_separately(){
+    let network = square();
+    assert_eq!(next_hop(&network, "1", "4", 0), "2");
+    assert_eq!(next_hop(&network, "1", "4", LATENCY), "3");
+    assert_eq!(next_hop(&network, "4", "1", LATENCY), "3");
+    let router = network.router(&"1".to_string()).unwrap();
+    assert_eq!(router.route_for(&"4".to_string(), 0).unwrap().metric, 2);
+    assert_eq!(router.route_for(&"4".to_string(), LATENCY).unwrap().metric, 2);
+    assert!(router.route_for(&"4".to_string(), 2).is_none());
+    assert!(network.violations.is_empty());
+
+    let restored = VirtualNetwork::restore(&network.freeze()).unwrap();
+    assert_eq!(next_hop(&restored, "1", "4", LATENCY), "3");
+}
+
+#[test]
+fn links_without_class_metric_are_unusable(){
+    let mut network = create(
+        &["1", "2", "3"],
+        &[(0, "1", "2", 1), (1, "2", "3", 1)]
+    );
+    for router in &mut network.routers{
+        router.add_class(LATENCY);
+    }
+    set_class_metric(&mut network, 0, "1", "2", 1);
+    network.tick_n(10);
+    let router = network.router(&"1".to_string()).unwrap();
+    assert_eq!(router.route_for(&"3".to_string(), 0).unwrap().metric, 2);
+    assert_eq!(router.route_for(&"2".to_string(), LATENCY).unwrap().metric, 1);
+    assert!(router.route_for(&"3".to_string(), LATENCY).is_none());
+}
+
+#[test]
+fn class_failure_does_not_affect_default(){
+    let mut network = square();
+    set_class_metric(&mut network, 3, "3", "4", INF);
+    network.tick_n(10);
+    assert_eq!(next_hop(&network, "1", "4", LATENCY), "2");
+    assert_eq!(network.router(&"1".to_string()).unwrap().route_for(&"4".to_string(), LATENCY).unwrap().metric, 20);
+    assert_eq!(next_hop(&network, "1", "4", 0), "2");
+    assert_eq!(next_hop(&network, "3", "4", 0), "4");
+}
+
+#[test]
+fn classes_share_seqno(){
+    let mut network = square();
+    // the route over 2 is not feasible in the latency class, 4 has to increase its seqno
+    set_class_metric(&mut network, 3, "3", "4", INF);
+    network.tick_n(10);
+    let dest = network.router(&"4".to_string()).unwrap();
+    assert_ne!(dest.seqno, 0);
+    assert_eq!(dest.class(LATENCY).unwrap().seqno, dest.seqno);
+    let router = network.router(&"1".to_string()).unwrap();
+    for class in [0, LATENCY]{
+        assert_eq!(router.route_for(&"4".to_string(), class).unwrap().source.data.seqno, dest.seqno);
+    }
+}
+
+#[test]
+fn removed_links_are_removed_from_classes(){
+    let mut network = square();
+    network.remove_link(&2);
+    network.tick_n(10);
+    let router = network.router(&"1".to_string()).unwrap();
+    assert!(!router.class(LATENCY).unwrap().links.contains_key(&2));
+    assert_eq!(next_hop(&network, "1", "3", LATENCY), "2");
+}